@@ -133,6 +133,15 @@ impl From<rodio::decoder::DecoderError> for QuicksilverError {
     }
 }
 
+#[doc(hidden)]
+#[cfg(feature = "sounds")]
+impl From<hound::Error> for QuicksilverError {
+    fn from(snd: hound::Error) -> QuicksilverError {
+        let sound_error: SoundError = snd.into();
+        sound_error.into()
+    }
+}
+
 #[doc(hidden)]
 #[cfg(feature = "rusttype")]
 impl From<FontError> for QuicksilverError {
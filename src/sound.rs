@@ -3,37 +3,146 @@
 //! On the desktop, currently all sounds are loaded into memory, but streaming sounds may be
 //! introduced in the future. On the web, it can be different from browser to browser
 
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod align;
+#[cfg(not(target_arch="wasm32"))]
+mod analysis;
+mod automation;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod biquad;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod bpm;
+#[cfg(not(target_arch="wasm32"))]
+mod cache;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod channels;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod chorus;
+mod curves;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod distortion;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod dynamics;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod edit;
+#[cfg(all(feature = "audio_graph", feature = "dsp", not(target_arch="wasm32")))]
+mod effects;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod envelope;
+mod events;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod filters;
+#[cfg(feature = "fingerprint")]
+mod fingerprint;
+#[cfg(all(feature = "flac", not(target_arch="wasm32")))]
+mod flac;
+mod format;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod gain;
+#[cfg(all(feature = "audio_graph", not(target_arch="wasm32")))]
+mod graph;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod key;
+mod loader;
+mod loop_points;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod loudness;
+mod metadata;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod modulation;
+#[cfg(all(feature = "monitor", not(target_arch="wasm32")))]
+mod monitor;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod padding;
+mod pcm;
+mod playlist;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod psola;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod room;
+#[cfg(not(target_arch="wasm32"))]
+mod sidechain;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod similarity;
+#[cfg(not(target_arch="wasm32"))]
+mod source;
+#[cfg(all(feature = "spectrogram", not(target_arch="wasm32")))]
+mod spectrogram;
+#[cfg(all(feature = "rustfft", not(target_arch="wasm32")))]
+mod spectrum;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod stereo;
+#[cfg(feature = "synth")]
+mod synth;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+mod vocoder;
+#[cfg(not(target_arch="wasm32"))]
+mod waveform;
+pub use self::automation::Envelope;
+#[cfg(not(target_arch="wasm32"))]
+pub use self::cache::SoundCache;
+pub use self::curves::SoundFadeCurve;
+#[cfg(all(feature = "audio_graph", feature = "dsp", not(target_arch="wasm32")))]
+pub use self::effects::{AudioEffect, GainEffect, LowPassEffect, ReverbEffect};
+pub use self::events::{PlayEvent, PlayEventReceiver};
+pub use self::format::AudioFormat;
+pub use self::loader::SoundLoader;
+pub use self::loop_points::LoopRegion;
+#[cfg(all(feature = "audio_graph", not(target_arch="wasm32")))]
+pub use self::graph::{AudioGraph, NodeId};
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+pub use self::key::{Mode, MusicKey, Pitch};
+pub use self::metadata::SoundMetadata;
+#[cfg(not(target_arch="wasm32"))]
+pub use self::pcm::PcmBuffer;
+pub use self::playlist::ShuffledPlaylist;
+#[cfg(all(feature = "dsp", not(target_arch="wasm32")))]
+pub use self::room::RoomModel;
+#[cfg(not(target_arch="wasm32"))]
+pub use self::sidechain::SideChainCompressor;
+#[cfg(feature = "synth")]
+pub use self::synth::{BeepGenerator, Waveform};
+
 use crate::{
     Result,
     error::QuicksilverError,
 };
+use self::pcm::encode_wav;
 use futures::{Future, future};
+use rand::{FromEntropy, Rng, SeedableRng, distributions::WeightedIndex, rngs::SmallRng};
 use std::{
     error::Error,
     fmt,
-    io::Error as IOError,
-    path::Path
+    io::{Error as IOError, ErrorKind},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration
 };
 #[cfg(not(target_arch="wasm32"))]
 use {
+    self::pcm::PcmCache,
+    self::source::{Automated, Controlled, FadeIn, FadeOut, GainRamp, LoopCounter, PingPong, PlaybackControls, RegionLoop, Repeat},
+    futures::{Stream, future::Either, stream},
     rodio::{
         self,
         Sink,
+        buffer::SamplesBuffer,
         decoder::{Decoder, DecoderError},
         source::{SamplesConverter, Source, Amplify},
     },
     std::{
-        fs::File,
+        fs::{self, File},
         io::{Cursor, Read},
-        sync::Arc
+        thread
     }
 };
 #[cfg(target_arch="wasm32")]
 use {
-    futures::Async,
-    std::io::ErrorKind,
+    self::events::EventBus,
     stdweb::{
         unstable::TryInto,
+        web::TypedArray,
         Value
     }
 };
@@ -48,12 +157,56 @@ use {
 pub struct Sound {
     #[cfg(not(target_arch="wasm32"))]
     val: Arc<Vec<u8>>,
+    #[cfg(not(target_arch="wasm32"))]
+    metadata: Option<Arc<SoundMetadata>>,
+    #[cfg(not(target_arch="wasm32"))]
+    decoded: PcmCache,
+    #[cfg(all(feature = "fingerprint", not(target_arch="wasm32")))]
+    fingerprint: [u8; 32],
     #[cfg(target_arch="wasm32")]
     sound: Value,
     volume: f32,
-    loop_sound: bool
+    start_gain: Option<f32>,
+    end_gain: Option<f32>,
+    fade_curve: SoundFadeCurve,
+    loop_count: u32,
+    loop_region: Option<LoopRegion>,
+    pitch_variance: Option<f32>,
+    loop_mode: LoopMode
+}
+
+// SAFETY: the `Value` of a web sound can only be used from the thread that created it. Without the
+// atomics target feature, wasm has no threads, so a sound can never actually reach another
+// thread. This has to be revisited if quicksilver ever supports threaded wasm.
+#[cfg(all(target_arch="wasm32", not(target_feature = "atomics")))]
+unsafe impl Send for Sound {}
+
+
+/// How a sound behaves when it reaches its end
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Play the sound once
+    None,
+    /// Start again from the beginning
+    Repeat,
+    /// Play backwards to the beginning, then forwards again, and so on
+    ///
+    /// On the web, sounds can't be played backwards, so this behaves like `Repeat`.
+    PingPong
 }
 
+impl From<bool> for LoopMode {
+    fn from(loop_sound: bool) -> LoopMode {
+        if loop_sound {
+            LoopMode::Repeat
+        } else {
+            LoopMode::None
+        }
+    }
+}
+
+#[cfg(not(target_arch="wasm32"))]
+type BoxedSource = Box<dyn Source<Item = f32> + Send>;
 
 #[cfg(target_arch="wasm32")]
 fn wasm_sound_error(error: &str) -> QuicksilverError {
@@ -62,42 +215,121 @@ fn wasm_sound_error(error: &str) -> QuicksilverError {
     error.into()
 }
 
+// The audio context shared by every sound on the page, created the first time it is needed
+//
+// Browsers only allow a handful of live audio contexts, so sounds must never make their own
+#[cfg(target_arch="wasm32")]
+fn audio_context() -> Value {
+    js! {
+        if (!window.__quicksilverAudioContext) {
+            const Context = window.AudioContext || window.webkitAudioContext;
+            window.__quicksilverAudioContext = new Context();
+        }
+        return window.__quicksilverAudioContext;
+    }
+}
+
+// How far ahead of the shared audio clock synchronized sounds are scheduled, in seconds
+#[cfg(target_arch="wasm32")]
+const SYNC_LEAD_SECONDS: f64 = 0.05;
+
 impl Sound {
     /// Start loading a sound from a given path
-    pub fn load(path: impl AsRef<Path>) -> impl Future<Item = Sound, Error = QuicksilverError> {
-        Sound::load_impl(path.as_ref())
+    ///
+    /// The returned loader is a future for the sound, and can also report how far along the load
+    /// is, for drawing a loading screen.
+    pub fn load(path: impl AsRef<Path>) -> SoundLoader {
+        SoundLoader::new(path.as_ref(), 0, Duration::ZERO)
+    }
+
+    /// Start loading a sound from a URL, finishing as soon as it has enough data to start playing
+    ///
+    /// On the web, `load` waits until the browser expects to play the whole file without
+    /// stopping, which can take a long time for long music tracks. This instead finishes once
+    /// playback can start, and the rest of the file keeps loading while the sound plays. If
+    /// playback catches up with the download, the sound waits for more data; it isn't reported
+    /// as paused. The loader's progress shows how much of the file has arrived.
+    ///
+    /// On desktop, files are always read all at once, so this is the same as `load`.
+    pub fn load_streaming_url(url: &str) -> SoundLoader {
+        SoundLoader::new(Path::new(url), 0, Duration::ZERO).streaming()
+    }
+
+    /// Start loading a sound from a given path, trying again if the load fails
+    ///
+    /// After a failed attempt, the load waits for `backoff` and tries again, doubling the wait
+    /// each time, up to `max_retries` extra attempts. If every attempt fails, the error from the
+    /// last one is returned. The waits happen while the loader is polled, so nothing blocks. On
+    /// desktop only input and output errors are retried; on the web any failure is retried,
+    /// because the browser doesn't report why a load failed.
+    pub fn load_with_retries(path: impl AsRef<Path>, max_retries: u32, backoff: Duration) -> SoundLoader {
+        SoundLoader::new(path.as_ref(), max_retries, backoff)
+    }
+
+    /// Start loading every sound in a directory
+    ///
+    /// Only files with a `.wav`, `.ogg`, `.mp3`, or `.flac` extension are loaded, and the sounds
+    /// are returned in the order of their sorted paths. If any of the files fails to load, the
+    /// errors for all of the failed files are returned together, with their paths, as a
+    /// `SoundError::MultiLoadError`.
+    ///
+    /// Directories can't be listed on the web, so this always fails there.
+    pub fn load_directory(dir: impl AsRef<Path>) -> impl Future<Item = Vec<Sound>, Error = QuicksilverError> {
+        Sound::load_directory_impl(dir.as_ref())
     }
 
     #[cfg(not(target_arch="wasm32"))]
-    fn load_impl(path: &Path) -> impl Future<Item = Sound, Error = QuicksilverError> {
-        future::result(load(path))
+    fn load_directory_impl(dir: &Path) -> impl Future<Item = Vec<Sound>, Error = QuicksilverError> {
+        future::result(load_directory(dir).map_err(QuicksilverError::from))
     }
 
     #[cfg(target_arch="wasm32")]
-    fn load_impl(path: &Path) -> impl Future<Item = Sound, Error = QuicksilverError> {
+    fn load_directory_impl(_dir: &Path) -> impl Future<Item = Vec<Sound>, Error = QuicksilverError> {
+        let error = IOError::new(ErrorKind::Other, "Directories can't be listed on the web");
+        future::err(SoundError::from(error).into())
+    }
+
+    /// Create a sound clip from raw PCM samples
+    ///
+    /// The samples are interleaved by channel (left, right, left, right, ... for stereo) and
+    /// should lie within `[-1, 1]`; anything outside of that range is clipped. The samples are
+    /// stored as a 16-bit WAV, so a clip made this way plays exactly like one loaded from a file.
+    pub fn from_pcm(samples: &[f32], sample_rate: u32, channels: u16) -> Result<Sound> {
+        Sound::from_wav_bytes(encode_wav(samples, sample_rate, channels)?)
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    fn from_wav_bytes(bytes: Vec<u8>) -> Result<Sound> {
+        Ok(from_bytes(bytes)?)
+    }
+
+    #[cfg(target_arch="wasm32")]
+    fn from_wav_bytes(bytes: Vec<u8>) -> Result<Sound> {
+        let bytes = TypedArray::<u8>::from(bytes.as_slice());
         let sound = js! {
-            const audio = new Audio(@{path.to_str().expect("Path must be stringifiable")});
-            audio.hasError = false;
-            audio.onerror = (error) => audio.hasError = true;
-            return audio;
+            const blob = new Blob([@{bytes}], { type: "audio/wav" });
+            return new Audio(URL.createObjectURL(blob));
         };
-        future::poll_fn(move || {
-            let error = js! ( return @{&sound}.hasError ).try_into();
-            let ready = js! ( return @{&sound}.readyState ).try_into();
-            match (error, ready) {
-                (Ok(false), Ok(4)) => Ok(Async::Ready(Sound {
-                    sound: sound.clone(),
-                    volume: 1f32,
-                    loop_sound: false
-                })),
-                (Ok(true), _) => Err(wasm_sound_error("Sound file not found or could not load")),
-                (Ok(false), Ok(_)) => Ok(Async::NotReady),
-                (Err(_), _) => Err(wasm_sound_error("Checking sound network state failed")),
-                (_, Err(_)) => Err(wasm_sound_error("Checking sound ready state failed")),
-            }
-        })
+        Ok(Sound::from_element(sound))
+    }
+
+    // Wrap an audio element with the default volume and looping settings
+    //
+    // Every web sound is created here, so none of them can be missing a setting
+    #[cfg(target_arch="wasm32")]
+    fn from_element(sound: Value) -> Sound {
+        Sound {
+            sound,
+            volume: 1f32,
+            start_gain: None,
+            end_gain: None,
+            fade_curve: SoundFadeCurve::Linear,
+            loop_count: 0,
+            loop_region: None,
+            pitch_variance: None,
+            loop_mode: LoopMode::None
+        }
     }
-    
 
     /// Get the volume of the sound clip instance
     ///
@@ -113,15 +345,137 @@ impl Sound {
     /// The volume is multiplicative, meaing 1 is the identity, 0 is silent, 2 is twice the
     /// amplitude, etc. Note that sound is not perceived linearly so results may not correspond as
     /// expected.
+    ///
+    /// On the web, volumes above 1 play at full volume.
     pub fn set_volume(&mut self, volume: f32) {
         self.volume = volume;
     }
 
+    /// Scale the volume of the sound clip by a random amount, within `variance` either way
+    ///
+    /// A variance of 0.1 multiplies the volume by a factor between 0.9 and 1.1, so repeated
+    /// sounds like footsteps don't all sound the same. The variance is clamped to `[0, 1]`, and
+    /// the new volume to `[0, 2]`. This scales the current volume, so calling it repeatedly
+    /// drifts further and further; randomize a clone of the sound for each playback instead.
+    pub fn randomize_volume(&mut self, variance: f32) {
+        self.randomize_volume_with(variance, &mut SmallRng::from_entropy());
+    }
+
+    /// Scale the volume of the sound clip by a random amount, chosen by a seed
+    ///
+    /// This is the same as `randomize_volume`, except that the same seed always gives the same
+    /// volume.
+    pub fn randomize_volume_seeded(&mut self, variance: f32, seed: u64) {
+        self.randomize_volume_with(variance, &mut SmallRng::seed_from_u64(seed));
+    }
+
+    /// Play the sound clip at a random pitch each time `play` is called
+    ///
+    /// Each playback is sped up or slowed down by a fresh random amount of up to
+    /// `semitone_variance` semitones either way, which changes its pitch. Together with
+    /// `randomize_volume`, this keeps repeated sounds from sounding mechanical. The variance is
+    /// clamped to `[0, 12]`, and a variance of 0 turns the variation off. Only `play` varies the
+    /// pitch; the other ways of playing the sound don't.
+    pub fn randomize_pitch(&mut self, semitone_variance: f32) {
+        let variance = semitone_variance.clamp(0.0, 12.0);
+        self.pitch_variance = Some(variance).filter(|&variance| variance > 0.0);
+    }
+
+    // The playback speed for a single play, with the pitch variation applied
+    fn random_speed(&self) -> f32 {
+        match self.pitch_variance {
+            Some(variance) => 2f32.powf(SmallRng::from_entropy().gen_range(-variance, variance) / 12.0),
+            None => 1.0
+        }
+    }
+
+    fn randomize_volume_with(&mut self, variance: f32, rng: &mut impl Rng) {
+        let variance = variance.clamp(0.0, 1.0);
+        if variance > 0.0 {
+            self.volume = (self.volume * (1.0 + rng.gen_range(-variance, variance))).clamp(0.0, 2.0);
+        }
+    }
+
+    /// Set the gain at the start of each playback of the sound clip, in decibels
+    ///
+    /// The gain ramps from the start gain to the end gain over the length of the clip, and
+    /// starts over each time a looping sound goes around. A gain that isn't set is 0 dB, which
+    /// leaves the sound unchanged. The gain is applied on top of the volume, and only when the
+    /// sound is played; the clip itself is unchanged.
+    ///
+    /// On the web, the gain is only updated a few times a second, and the sound can't go past
+    /// full volume.
+    pub fn set_start_gain(&mut self, db: f32) -> &mut Sound {
+        self.start_gain = Some(db);
+        self
+    }
+
+    /// Set the gain at the end of each playback of the sound clip, in decibels
+    ///
+    /// See `set_start_gain` for how the gain is applied.
+    pub fn set_end_gain(&mut self, db: f32) -> &mut Sound {
+        self.end_gain = Some(db);
+        self
+    }
+
+    /// Set the shape of the fades used by `play_fade_in` and `play_fade_out`
+    ///
+    /// Fades are `SoundFadeCurve::Linear` by default.
+    pub fn set_fade_curve(&mut self, curve: SoundFadeCurve) {
+        self.fade_curve = curve;
+    }
+
+    /// Get the shape of the fades used by `play_fade_in` and `play_fade_out`
+    pub fn fade_curve(&self) -> SoundFadeCurve {
+        self.fade_curve
+    }
+
     /// Set looping sound
     /// 
-    /// If set sound will replay after it is finished.
-    pub fn set_loop_sound(&mut self, loop_sound: bool) {
-        self.loop_sound = loop_sound;
+    /// If set sound will replay after it is finished. Passing `true` or `false` is the same as
+    /// passing `LoopMode::Repeat` or `LoopMode::None`.
+    pub fn set_loop_sound(&mut self, loop_mode: impl Into<LoopMode>) {
+        self.loop_mode = loop_mode.into();
+    }
+
+    /// Get how the sound behaves when it reaches its end
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    /// Set how many times a looping sound plays before it stops
+    ///
+    /// A count of 0, the default, loops forever. A count of 1 plays the sound once, as if it
+    /// didn't loop. For `LoopMode::PingPong`, a trip forwards and back again counts once. The
+    /// count has no effect on a sound that doesn't loop.
+    pub fn set_loop_count(&mut self, count: u32) {
+        self.loop_count = count;
+    }
+
+    /// Get how many times a looping sound plays before it stops, or 0 if it loops forever
+    pub fn loop_count(&self) -> u32 {
+        self.loop_count
+    }
+
+    /// Check if the sound file declared any metadata, like its title or artist
+    ///
+    /// Metadata is read from OGG Vorbis, FLAC, and MP3 files when they are loaded. Other formats
+    /// (like WAV) and all sounds on the web have no metadata.
+    pub fn has_metadata(&self) -> bool {
+        self.metadata().is_some()
+    }
+
+    /// Get the metadata declared by the sound file, like its title or artist
+    ///
+    /// Metadata is read from OGG Vorbis, FLAC, and MP3 files when they are loaded. Other formats
+    /// (like WAV) and all sounds on the web have no metadata.
+    pub fn metadata(&self) -> Option<SoundMetadata> {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.metadata.as_ref().map(|metadata| metadata.as_ref().clone())
+        }
+        #[cfg(target_arch="wasm32")] {
+            None
+        }
     }
 
     #[cfg(not(target_arch="wasm32"))]
@@ -129,6 +483,148 @@ impl Sound {
         Ok(Decoder::new(Cursor::new(self.clone()))?.amplify(self.volume).convert_samples())
     }
 
+    // The full source for a single playback, with the volume and looping settings applied, and
+    // the counter of the passes it makes through a loop
+    #[cfg(not(target_arch="wasm32"))]
+    fn playback_source(&self) -> Result<(BoxedSource, LoopCounter)> {
+        let (source, passes): (BoxedSource, _) = match (self.loop_mode, self.region_samples()) {
+            (LoopMode::None, _) => (Box::new(self.get_source()?), LoopCounter::default()),
+            (LoopMode::Repeat, Some((start, end))) => {
+                let source = RegionLoop::new(&self.pcm(), self.volume, start, end);
+                let passes = source.passes();
+                (Box::new(source), passes)
+            }
+            (LoopMode::Repeat, None) => {
+                let sound = self.clone();
+                let source = Repeat::new(self.get_source()?, move || {
+                    sound.get_source().expect("Sound data is validated when the Sound is created")
+                });
+                let passes = source.passes();
+                (Box::new(source), passes)
+            }
+            (LoopMode::PingPong, _) => {
+                let source = PingPong::new(&self.pcm(), self.volume);
+                let passes = source.passes();
+                (Box::new(source), passes)
+            }
+        };
+        Ok((self.ramp_gain(source, || self.pass_lengths(), &passes), passes))
+    }
+
+    // Ramp the gain of a source from the start gain to the end gain over each of its passes
+    //
+    // The lengths of the passes are only worked out if there is a gain to ramp
+    #[cfg(not(target_arch="wasm32"))]
+    fn ramp_gain(&self, source: BoxedSource, lengths: impl FnOnce() -> (usize, usize), passes: &LoopCounter) -> BoxedSource {
+        if self.start_gain.is_none() && self.end_gain.is_none() {
+            return source;
+        }
+        let (start, end) = (self.start_gain.unwrap_or(0.0), self.end_gain.unwrap_or(0.0));
+        Box::new(GainRamp::new(source, start, end, lengths(), passes.clone()))
+    }
+
+    // Wrap a playback of this sound, after the given wrapper, so a stop handle can control it
+    #[cfg(not(target_arch="wasm32"))]
+    fn controlled_source(&self, wrap: impl FnOnce(BoxedSource) -> BoxedSource) -> Result<(Controlled<BoxedSource>, Arc<PlaybackControls>)> {
+        let controls = PlaybackControls::new();
+        let (source, passes) = self.playback_source()?;
+        let source = Controlled::new(wrap(source), controls.clone())
+            .counting_loops(passes)
+            .limit_loops(self.loop_count);
+        Ok((source, controls))
+    }
+
+    // Apply the pitch variation for a single play, if there is one
+    #[cfg(not(target_arch="wasm32"))]
+    fn vary_pitch(&self, source: BoxedSource) -> BoxedSource {
+        match self.pitch_variance {
+            Some(_) => Box::new(source.speed(self.random_speed())),
+            None => source
+        }
+    }
+
+    #[cfg(not(target_arch="wasm32"))]
+    fn play_controlled(&self, wrap: impl FnOnce(BoxedSource) -> BoxedSource) -> Result<StopHandle> {
+        let (source, controls) = self.controlled_source(wrap)?;
+        play_detached(source)?;
+        StopHandle::new(controls)
+    }
+
+    // Create a new audio element to play this sound clip once, with the looping setting applied
+    #[cfg(target_arch="wasm32")]
+    fn clone_element(&self) -> Value {
+        js! {
+            let snd = @{&self.sound}.cloneNode();
+            snd.volume = Math.max(0, Math.min(1, @{f64::from(self.volume)}));
+            snd.loop = @{self.loop_mode != LoopMode::None};
+            const region = @{self.loop_region.filter(|_| self.loop_mode == LoopMode::Repeat).map(|region| vec![region.start.as_secs_f64(), region.end.as_secs_f64()])};
+            if (region !== null) {
+                snd.addEventListener("timeupdate", () => {
+                    if (snd.currentTime >= region[1]) { snd.currentTime = region[0]; }
+                });
+            }
+            const loopCount = @{self.loop_count};
+            if (snd.loop && loopCount > 0) {
+                let passes = 1;
+                let lastTime = 0;
+                snd.loop = loopCount > 1;
+                snd.addEventListener("timeupdate", () => {
+                    if (snd.currentTime < lastTime) { passes += 1; }
+                    lastTime = snd.currentTime;
+                    if (passes >= loopCount) { snd.loop = false; }
+                });
+            }
+            snd.__fadeCurve = [t => t, t => t * t, t => t * (2 - t), t => t * t * (3 - 2 * t)][@{self.fade_curve as u32}];
+            const startGain = @{self.start_gain.map(f64::from)};
+            const endGain = @{self.end_gain.map(f64::from)};
+            if (startGain !== null || endGain !== null) {
+                const base = snd.volume;
+                const ramp = () => {
+                    // The gain ramps over the whole clip, unless only part of it is played
+                    const span = snd.__rampSpan || [0, snd.duration];
+                    const progress = span[1] > span[0] ? Math.min(1, Math.max(0, (snd.currentTime - span[0]) / (span[1] - span[0]))) : 0;
+                    const db = (startGain || 0) + ((endGain || 0) - (startGain || 0)) * progress;
+                    snd.volume = Math.min(1, base * Math.pow(10, db / 20));
+                };
+                ramp();
+                snd.addEventListener("timeupdate", ramp);
+            }
+            return snd;
+        }
+    }
+
+    // The number of samples in the first pass through a playback, and in each pass after it
+    //
+    // Loop regions and ping-pong loops are decoded to be played, so their lengths are exact; the
+    // length of a whole clip comes from `sample_count_hint` instead, so it isn't decoded just to
+    // be measured
+    #[cfg(not(target_arch="wasm32"))]
+    fn pass_lengths(&self) -> (usize, usize) {
+        match (self.loop_mode, self.region_samples()) {
+            (LoopMode::PingPong, _) => {
+                let pcm = self.pcm();
+                let length = 2 * pcm.frames().saturating_sub(1) * pcm.channels as usize;
+                (length, length)
+            }
+            (_, Some((start, end))) => (end, end - start),
+            _ => {
+                let length = self.sample_count_hint();
+                (length, length)
+            }
+        }
+    }
+
+    // The samples that the loop region covers, if it applies
+    #[cfg(not(target_arch="wasm32"))]
+    fn region_samples(&self) -> Option<(usize, usize)> {
+        let region = self.loop_region.filter(|_| self.loop_mode == LoopMode::Repeat)?;
+        let pcm = self.pcm();
+        let sample_at = |time: Duration| {
+            ((time.as_secs_f64() * f64::from(pcm.sample_rate)).round() as usize).min(pcm.frames()) * pcm.channels as usize
+        };
+        Some((sample_at(region.start), sample_at(region.end)))
+    }
+
     /// Play the sound clip at its current volume
     ///
     /// The sound clip can be played over itself.
@@ -136,29 +632,376 @@ impl Sound {
     /// Future changes in volume will not change the sound emitted by this method.
     pub fn play(&self) -> Result<StopHandle> {
         #[cfg(not(target_arch="wasm32"))] {
-            let device = match rodio::default_output_device() {
-                Some(device) => device,
-                None => return Err(SoundError::NoOutputAvailable.into())
-            };
-            let sink = Sink::new(&device);
-            if self.loop_sound {
-                sink.append(self.get_source()?.repeat_infinite());
-            } else {
-                sink.append(self.get_source()?);
-            }            
-            StopHandle::new(sink)
+            self.play_controlled(|source| self.vary_pitch(source))
         }
         #[cfg(target_arch="wasm32")] {
-            let sound: Value = js! {
-                let snd = @{&self.sound}.cloneNode();
-                snd.loop = @{self.loop_sound};
+            let sound = self.clone_element();
+            if self.pitch_variance.is_some() {
+                js! {
+                    const snd = @{&sound};
+                    snd.preservesPitch = snd.mozPreservesPitch = snd.webkitPreservesPitch = false;
+                    snd.playbackRate = @{self.random_speed()};
+                }
+            }
+            js! { @{&sound}.play(); }
+            StopHandle::new(sound)
+        }
+    }
+
+    /// Play the sound clip, fading it in from silence over the given duration
+    ///
+    /// The fade only applies to this playback; the clip itself is unchanged. On the web, the
+    /// volume is raised in small steps rather than smoothly.
+    pub fn play_fade_in(&self, duration: Duration) -> Result<StopHandle> {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.play_controlled(|source| Box::new(FadeIn::new(source, duration, self.fade_curve)))
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sound = self.clone_element();
+            js! {
+                const snd = @{&sound};
+                const duration = @{duration.as_secs_f64() * 1000.0};
+                const target = snd.volume;
+                const start = performance.now();
+                snd.volume = 0;
                 snd.play();
-                return snd;
+                const timer = setInterval(() => {
+                    const progress = duration > 0 ? Math.min(1, (performance.now() - start) / duration) : 1;
+                    snd.volume = target * snd.__fadeCurve(progress);
+                    if (progress >= 1) { clearInterval(timer); }
+                }, 20);
+            }
+            StopHandle::new(sound)
+        }
+    }
+
+    /// Play the sound clip, fading out to silence over `duration`
+    ///
+    /// A sound that does not loop fades out over the last `duration` of the clip. Stopping or
+    /// dropping the returned handle also fades the sound out over `duration` before it stops,
+    /// instead of cutting it off. On the web, the volume is lowered in small steps rather than
+    /// smoothly.
+    pub fn play_fade_out(&self, duration: Duration) -> Result<StopHandle> {
+        #[cfg(not(target_arch="wasm32"))] {
+            let handle = self.play_controlled(|source| match self.loop_mode {
+                LoopMode::None => Box::new(FadeOut::new(source, duration, self.pcm().samples.len(), self.fade_curve)),
+                _ => source
+            })?;
+            handle.controls.set_stop_fade(duration, self.fade_curve);
+            Ok(handle)
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sound = self.clone_element();
+            js! {
+                const snd = @{&sound};
+                const duration = @{duration.as_secs_f64()};
+                const target = snd.volume;
+                snd.addEventListener("timeupdate", () => {
+                    if (snd.loop || snd.__stopping || duration <= 0) { return; }
+                    const left = snd.duration - snd.currentTime;
+                    const progress = 1 - Math.max(0, Math.min(1, left / duration));
+                    snd.volume = target * (1 - snd.__fadeCurve(progress));
+                });
+                snd.play();
+            }
+            let mut handle = StopHandle::new(sound)?;
+            handle.stop_fade = duration;
+            Ok(handle)
+        }
+    }
+
+    /// Play the sound clip with its volume following an envelope
+    ///
+    /// The envelope only applies to this playback; the clip itself is unchanged, unlike with
+    /// `volume_automation`. The envelope's times count from when the sound starts playing, and
+    /// keep counting through any loops. Fails if the envelope has no points, its points aren't
+    /// sorted, or any of its volumes are negative.
+    ///
+    /// On the web, the volume is changed in small steps rather than smoothly, and can't go above
+    /// the clip's own volume.
+    pub fn play_with_envelope(&self, envelope: &Envelope) -> Result<StopHandle> {
+        envelope.validate()?;
+        #[cfg(not(target_arch="wasm32"))] {
+            self.play_controlled(|source| Box::new(Automated::new(source, envelope.clone())))
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sound = self.clone_element();
+            let times: Vec<f64> = envelope.points.iter().map(|(time, _)| time.as_secs_f64()).collect();
+            let volumes: Vec<f64> = envelope.points.iter().map(|&(_, volume)| f64::from(volume)).collect();
+            js! {
+                const snd = @{&sound};
+                const times = @{times};
+                const volumes = @{volumes};
+                const target = snd.volume;
+                const volumeAt = (time) => {
+                    const next = times.findIndex((point) => point > time);
+                    if (next === -1) { return volumes[volumes.length - 1]; }
+                    if (next === 0) { return volumes[0]; }
+                    const progress = (time - times[next - 1]) / (times[next] - times[next - 1]);
+                    return volumes[next - 1] + (volumes[next] - volumes[next - 1]) * progress;
+                };
+                // Only the time spent playing counts, so pausing holds the envelope in place
+                let elapsed = 0;
+                let last = performance.now();
+                snd.volume = Math.min(1, target * volumeAt(0));
+                snd.play();
+                const timer = setInterval(() => {
+                    const now = performance.now();
+                    if (!snd.paused) { elapsed += (now - last) / 1000; }
+                    last = now;
+                    if (snd.ended || snd.__stopping) { clearInterval(timer); return; }
+                    snd.volume = Math.min(1, target * volumeAt(elapsed));
+                }, 20);
+            }
+            StopHandle::new(sound)
+        }
+    }
+
+    /// Play the sound clip, and then play `next` as soon as it ends
+    ///
+    /// The returned handle controls whichever of the two clips is playing, and only finishes
+    /// once `next` has finished. Each clip is played the way `play` would play it, with its own
+    /// loop region and loop count. If this clip loops without a loop count, it never ends, so
+    /// `next` is never played. On the web, `next` only keeps its volume and whether it loops.
+    pub fn play_then(&self, next: Sound) -> Result<StopHandle> {
+        #[cfg(not(target_arch="wasm32"))] {
+            // The handle only controls the pair, so the controls of each clip are left unused
+            let (first, _) = self.controlled_source(|source| self.vary_pitch(source))?;
+            let (second, _) = next.controlled_source(|source| next.vary_pitch(source))?;
+            play_source(rodio::source::from_iter(vec![first, second]))
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sound = self.clone_element();
+            let next = next.clone_element();
+            js! {
+                const snd = @{&sound};
+                const next = @{next};
+                let switched = false;
+                snd.addEventListener("ended", (event) => {
+                    if (snd.__stopping || switched) { return; }
+                    switched = true;
+                    // Carry on with the next sound, without the handle seeing the end
+                    event.stopImmediatePropagation();
+                    snd.src = next.src;
+                    snd.volume = next.volume;
+                    snd.loop = next.loop;
+                    snd.play();
+                });
+                snd.play();
+            }
+            StopHandle::new(sound)
+        }
+    }
+
+    /// Play the sound clip transposed by a number of semitones
+    ///
+    /// The clip is sped up or slowed down so its pitch moves by `semitones`: 12 plays an octave
+    /// up at twice the speed, and -7 plays a perfect fifth down. The pitch can move by at most
+    /// four octaves either way, the range browsers support.
+    pub fn play_at_pitch(&self, semitones: i32) -> Result<StopHandle> {
+        let rate = semitones_to_rate(semitones)?;
+        #[cfg(not(target_arch="wasm32"))] {
+            self.play_controlled(|source| Box::new(source.speed(rate)))
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sound = self.clone_element();
+            js! {
+                const snd = @{&sound};
+                snd.preservesPitch = snd.mozPreservesPitch = snd.webkitPreservesPitch = false;
+                snd.playbackRate = @{rate};
+                snd.play();
+            }
+            StopHandle::new(sound)
+        }
+    }
+
+    /// Play only the part of the sound clip between `start` and `end`
+    ///
+    /// The range is played once, even if the sound is set to loop, and ends early if the clip is
+    /// shorter than `end`. The start and end gains ramp over the range instead of the whole clip.
+    /// Returns an error if `start` is not before `end`. On the web, the end of the range is only
+    /// checked a few times a second, so a little more may be played.
+    pub fn play_range(&self, start: Duration, end: Duration) -> Result<StopHandle> {
+        if start >= end {
+            return Err(SoundError::InvalidParameter(format!("The range must start before it ends, not at {:?} to {:?}", start, end)).into());
+        }
+        #[cfg(not(target_arch="wasm32"))] {
+            let pcm = self.pcm();
+            let channels = pcm.channels as usize;
+            let sample_at = |time: Duration| {
+                ((time.as_secs_f64() * f64::from(pcm.sample_rate)) as usize).min(pcm.frames()) * channels
             };
+            let samples = pcm.samples[sample_at(start)..sample_at(end)].to_vec();
+            let length = samples.len();
+            let source = Box::new(SamplesBuffer::new(pcm.channels, pcm.sample_rate, samples).amplify(self.volume));
+            let source = self.ramp_gain(source, || (length, length), &LoopCounter::default());
+            let controls = PlaybackControls::new();
+            play_detached(Controlled::new(source, controls.clone()))?;
+            StopHandle::new(controls)
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sound = self.clone_element();
+            js! {
+                const snd = @{&sound};
+                const end = @{end.as_secs_f64()};
+                snd.loop = false;
+                snd.currentTime = @{start.as_secs_f64()};
+                snd.__rampSpan = [@{start.as_secs_f64()}, end];
+                snd.addEventListener("timeupdate", () => {
+                    if (snd.currentTime >= end && !snd.__stopping) {
+                        snd.__stopping = true;
+                        snd.pause();
+                        snd.dispatchEvent(new Event("ended"));
+                    }
+                });
+                snd.play();
+            }
             StopHandle::new(sound)
         }
     }
-    
+
+    /// Play one of the sound clips, chosen at random, returning its index and its handle
+    ///
+    /// Every clip is equally likely to be chosen, which is useful for varying sounds that repeat,
+    /// like footsteps or impacts. Fails if there are no sounds to choose from.
+    pub fn play_random_from(sounds: &[Sound]) -> Result<(usize, StopHandle)> {
+        let index = random_index(sounds.len(), &mut SmallRng::from_entropy())?;
+        Ok((index, sounds[index].play()?))
+    }
+
+    /// Play one of the sound clips, chosen at random with the given weights
+    ///
+    /// Each clip is chosen in proportion to its weight, so a clip with a weight of 2 is played
+    /// twice as often as one with a weight of 1, and a weight of 0 is never played. Fails if
+    /// there isn't one weight for every sound, if any weight is negative, or if the weights add
+    /// up to 0.
+    pub fn play_weighted_random(sounds: &[Sound], weights: &[f32]) -> Result<(usize, StopHandle)> {
+        if sounds.len() != weights.len() {
+            return Err(SoundError::InvalidParameter(format!(
+                "There must be one weight for each of the {} sounds, not {}", sounds.len(), weights.len())).into());
+        }
+        let index = weighted_index(weights, &mut SmallRng::from_entropy())?;
+        Ok((index, sounds[index].play()?))
+    }
+
+    /// Play several sound clips so that they start at exactly the same time
+    ///
+    /// Calling `play` on each sound in turn can leave a small gap between them; this instead
+    /// starts them together, each with its own volume and looping settings. The returned stop
+    /// handles are in the same order as the sounds, and each one controls only its own sound.
+    ///
+    /// On the web, the sounds are all started together, a moment after the call, by a timer on the
+    /// page's shared audio clock. That is as close as the browser allows, but not sample-accurate.
+    pub fn play_synchronized(sounds: &[Sound]) -> Result<Vec<StopHandle>> {
+        #[cfg(not(target_arch="wasm32"))] {
+            if sounds.is_empty() {
+                return Ok(Vec::new());
+            }
+            let mut handles = Vec::with_capacity(sounds.len());
+            let mut mixed: Option<BoxedSource> = None;
+            for sound in sounds {
+                let (source, controls) = sound.controlled_source(|source| source)?;
+                mixed = Some(match mixed {
+                    Some(mixed) => Box::new(mixed.mix(source)),
+                    None => Box::new(source)
+                });
+                handles.push(StopHandle::new(controls)?);
+            }
+            if let Some(mixed) = mixed {
+                play_detached(mixed)?;
+            }
+            Ok(handles)
+        }
+        #[cfg(target_arch="wasm32")] {
+            let clones: Vec<Value> = sounds.iter().map(Sound::clone_element).collect();
+            js! {
+                const clones = @{clones.clone()};
+                const context = @{audio_context()};
+                // Sounds stopped before the timer fires stay stopped
+                const start = () => clones.forEach((snd) => { if (!snd.__stopping) { snd.play(); } });
+                if (context.state !== "running") {
+                    // The clock doesn't run until the page is allowed to play audio
+                    context.resume();
+                    start();
+                    return;
+                }
+                // A silent one-sample buffer ends on the same tick of the audio clock for everyone
+                const tick = context.createBufferSource();
+                tick.buffer = context.createBuffer(1, 1, context.sampleRate);
+                tick.connect(context.destination);
+                tick.onended = start;
+                tick.start(context.currentTime + @{SYNC_LEAD_SECONDS});
+            }
+            clones.into_iter().map(StopHandle::new).collect()
+        }
+    }
+
+    /// Play this sound clip in the left channel and another in the right, on the same timeline
+    ///
+    /// Both clips should be mono; a clip with more channels is mixed down first. The clips are
+    /// played once, with their own volumes, and the shorter one is followed by silence. They
+    /// must have the same sample rate. The two handles control the same playback, so pausing,
+    /// resuming or stopping either one affects both.
+    ///
+    /// On the web, the clips are routed through a channel merger on the page's shared audio
+    /// context, and started within the same script call.
+    pub fn play_interleaved(&self, other: &Sound) -> Result<(StopHandle, StopHandle)> {
+        #[cfg(not(target_arch="wasm32"))] {
+            let (left, right) = (self.pcm(), other.pcm());
+            if left.sample_rate != right.sample_rate {
+                return Err(SoundError::InvalidParameter(format!("The sample rates must match, not {} and {}", left.sample_rate, right.sample_rate)).into());
+            }
+            let mono = |pcm: &PcmBuffer, volume: f32| -> Vec<f32> {
+                pcm.samples.chunks(pcm.channels as usize)
+                    .map(|frame| volume * frame.iter().sum::<f32>() / frame.len() as f32)
+                    .collect()
+            };
+            let (left, right) = (mono(&left, self.volume), mono(&right, other.volume));
+            let samples = (0..left.len().max(right.len()))
+                .flat_map(|frame| [left.get(frame).cloned().unwrap_or(0.0), right.get(frame).cloned().unwrap_or(0.0)])
+                .collect::<Vec<f32>>();
+            let controls = PlaybackControls::new();
+            play_detached(Controlled::new(SamplesBuffer::new(2, self.pcm().sample_rate, samples), controls.clone()))?;
+            Ok((StopHandle::new(controls.clone())?, StopHandle::new(controls)?))
+        }
+        #[cfg(target_arch="wasm32")] {
+            let (left, right) = (self.clone_element(), other.clone_element());
+            js! {
+                const left = @{&left};
+                const right = @{&right};
+                left.loop = false;
+                right.loop = false;
+                const context = @{audio_context()};
+                if (context.state !== "running") {
+                    // Elements that play through a suspended context are silent
+                    context.resume();
+                }
+                const merger = context.createChannelMerger(2);
+                context.createMediaElementSource(left).connect(merger, 0, 0);
+                context.createMediaElementSource(right).connect(merger, 0, 1);
+                merger.connect(context.destination);
+                left.partner = right;
+                right.partner = left;
+                left.play();
+                right.play();
+            }
+            Ok((StopHandle::new(left)?, StopHandle::new(right)?))
+        }
+    }
+
+    /// Turn the sound clip into an asynchronous stream of its samples, without playing it
+    ///
+    /// The samples are interleaved by channel and have the clip's volume applied. The stream
+    /// never ends if the sound is set to loop. If the sound cannot be decoded, the stream yields
+    /// a single error.
+    #[cfg(not(target_arch="wasm32"))]
+    pub fn into_async_stream(self) -> impl Stream<Item = f32, Error = QuicksilverError> {
+        match self.playback_source() {
+            Ok((source, _)) => Either::A(stream::iter_ok(source)),
+            Err(err) => Either::B(stream::once(Err(err)))
+        }
+    }
+
     #[cfg(not(target_arch="wasm32"))]
     //Play a silent sound so rodio startup doesn't interfere with application
     //Unfortunately this means even apps that don't use sound eat the startup penalty but it's not a
@@ -170,15 +1013,124 @@ impl Sound {
     }
 }
 
+// Start playing a source on a new sink attached to the default output device
+//
+// The sink is detached, so the source keeps playing until it ends or its stop handles stop it
+#[cfg(not(target_arch="wasm32"))]
+fn play_detached(source: impl Source<Item = f32> + Send + 'static) -> Result<()> {
+    let device = match rodio::default_output_device() {
+        Some(device) => device,
+        None => return Err(SoundError::NoOutputAvailable.into())
+    };
+    let sink = Sink::new(&device);
+    sink.append(source);
+    sink.detach();
+    Ok(())
+}
+
+#[cfg(not(target_arch="wasm32"))]
+fn play_source(source: impl Source<Item = f32> + Send + 'static) -> Result<StopHandle> {
+    let controls = PlaybackControls::new();
+    play_detached(Controlled::new(source, controls.clone()))?;
+    StopHandle::new(controls)
+}
+
 #[cfg(not(target_arch="wasm32"))]
-fn load(path: &Path) -> Result<Sound> {
+fn load(path: &Path) -> std::result::Result<Sound, SoundError> {
     let mut bytes = Vec::new();
     File::open(path)?.read_to_end(&mut bytes)?;
-    let val = Arc::new(bytes);
+    from_bytes(bytes)
+}
+
+// The playback speed that moves the pitch of a sound by a number of semitones
+fn semitones_to_rate(semitones: i32) -> Result<f32> {
+    const MAX_SEMITONES: i32 = 48;
+    if semitones.abs() > MAX_SEMITONES {
+        return Err(SoundError::InvalidParameter(format!(
+            "The pitch can move by at most {} semitones, not {}", MAX_SEMITONES, semitones)).into());
+    }
+    Ok(2f64.powf(f64::from(semitones) / 12.0) as f32)
+}
+
+// Choose an index below `count`, with every index equally likely
+fn random_index(count: usize, rng: &mut impl Rng) -> Result<usize> {
+    if count == 0 {
+        return Err(SoundError::InvalidParameter("There are no sounds to choose from".to_owned()).into());
+    }
+    Ok(rng.gen_range(0, count))
+}
+
+// Choose an index into the weights, with each index as likely as its weight
+fn weighted_index(weights: &[f32], rng: &mut impl Rng) -> Result<usize> {
+    let distribution = WeightedIndex::new(weights)
+        .map_err(|err| SoundError::InvalidParameter(format!("The weights {:?} can't be chosen from: {}", weights, err)))?;
+    Ok(rng.sample(distribution))
+}
+
+// The file extensions that load_directory treats as sounds
+#[cfg(not(target_arch="wasm32"))]
+const SOUND_EXTENSIONS: &[&str] = &["wav", "ogg", "mp3", "flac"];
+
+#[cfg(not(target_arch="wasm32"))]
+fn load_directory(dir: &Path) -> std::result::Result<Vec<Sound>, SoundError> {
+    // Each file is read and decoded on its own thread
+    let loading: Vec<_> = sound_paths(dir)?.into_iter()
+        .map(|path| (path.clone(), thread::spawn(move || load(&path))))
+        .collect();
+    let mut sounds = Vec::new();
+    let mut errors = Vec::new();
+    for (path, handle) in loading {
+        match handle.join().expect("A sound loading thread panicked") {
+            Ok(sound) => sounds.push(sound),
+            Err(err) => errors.push((path, err))
+        }
+    }
+    if errors.is_empty() {
+        Ok(sounds)
+    } else {
+        Err(SoundError::MultiLoadError(errors))
+    }
+}
+
+// The sound files directly inside a directory, in order
+#[cfg(not(target_arch="wasm32"))]
+fn sound_paths(dir: &Path) -> std::result::Result<Vec<PathBuf>, SoundError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_sound = path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| SOUND_EXTENSIONS.iter().any(|known| extension.eq_ignore_ascii_case(known)));
+        if is_sound && !path.is_dir() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(not(target_arch="wasm32"))]
+fn from_bytes(bytes: Vec<u8>) -> std::result::Result<Sound, SoundError> {
+    from_shared_bytes(Arc::new(bytes))
+}
+
+#[cfg(not(target_arch="wasm32"))]
+fn from_shared_bytes(val: Arc<Vec<u8>>) -> std::result::Result<Sound, SoundError> {
+    let metadata = metadata::parse(&val).map(Arc::new);
     let sound = Sound {
-        val,
+        metadata,
+        decoded: PcmCache::default(),
+        #[cfg(feature = "fingerprint")]
+        fingerprint: fingerprint::sha256(&val),
         volume: 1f32,
-        loop_sound: false
+        start_gain: None,
+        end_gain: None,
+        fade_curve: SoundFadeCurve::Linear,
+        loop_count: 0,
+        loop_region: loop_points::smpl_loop_region(&val),
+        pitch_variance: None,
+        loop_mode: LoopMode::None,
+        val
     };
     Decoder::new(Cursor::new(sound.clone()))?;
     Ok(sound)
@@ -192,7 +1144,18 @@ impl AsRef<[u8]> for Sound {
     }
 }
 
-#[derive(Debug)]
+/// Convert a sound clip into a rodio `Source`, for building custom mixing pipelines on rodio
+///
+/// The volume and looping settings are baked in at the time of conversion; changing them on the
+/// `Sound` afterwards does not affect the source.
+#[cfg(not(target_arch="wasm32"))]
+impl From<Sound> for Box<dyn Source<Item = f32> + Send> {
+    fn from(sound: Sound) -> Box<dyn Source<Item = f32> + Send> {
+        sound.playback_source().expect("Sound data is validated when the Sound is created").0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 /// An error generated when loading a sound
 pub enum SoundError {
     /// The sound file is not in an format that can be played
@@ -200,12 +1163,21 @@ pub enum SoundError {
     /// No output device was found to play the sound
     NoOutputAvailable,
     /// The Sound was not found or could not be loaded
-    IOError(IOError)
+    IOError(IoErrorWrapper),
+    /// An argument passed to a sound function was out of range or inconsistent
+    InvalidParameter(String),
+    /// Several sounds failed to load, such as when loading a directory, each with the path of the
+    /// file that failed
+    MultiLoadError(Vec<(PathBuf, SoundError)>)
 }
 
 impl fmt::Display for SoundError  {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+        match self {
+            // The description of an IO error with a custom message doesn't include the message
+            SoundError::IOError(err) => write!(f, "{}", **err),
+            _ => write!(f, "{}", self.description())
+        }
     }
 }
 
@@ -214,15 +1186,19 @@ impl Error for SoundError {
         match self {
             SoundError::UnrecognizedFormat => "The sound file format was not recognized",
             SoundError::NoOutputAvailable => "There was no output device available for playing",
-            SoundError::IOError(err) => err.description()
+            SoundError::IOError(err) => err.description(),
+            SoundError::InvalidParameter(message) => message.as_str(),
+            SoundError::MultiLoadError(_) => "Some of the sounds could not be loaded"
         }
     }
 
     fn cause(&self) -> Option<&dyn Error> {
         match self {
             SoundError::UnrecognizedFormat
-                | SoundError::NoOutputAvailable => None,
-            SoundError::IOError(err) => Some(err)
+                | SoundError::NoOutputAvailable
+                | SoundError::InvalidParameter(_) => None,
+            SoundError::IOError(err) => Some(&**err),
+            SoundError::MultiLoadError(errors) => errors.first().map(|(_, err)| err as &dyn Error)
         }
     }
 
@@ -241,39 +1217,544 @@ impl From<DecoderError> for SoundError {
 #[doc(hidden)]
 impl From<IOError> for SoundError {
     fn from(err: IOError) -> SoundError {
-        SoundError::IOError(err)
+        SoundError::IOError(IoErrorWrapper(Arc::new(err)))
+    }
+}
+
+/// Sound and input/output errors are unwrapped; any other error becomes an `IOError` of kind
+/// `Other`, with the error's message
+impl From<Box<dyn Error + Send + Sync>> for SoundError {
+    fn from(err: Box<dyn Error + Send + Sync>) -> SoundError {
+        let err = match err.downcast::<SoundError>() {
+            Ok(err) => return *err,
+            Err(err) => err
+        };
+        match err.downcast::<IOError>() {
+            Ok(err) => (*err).into(),
+            Err(err) => IOError::other(err.to_string()).into()
+        }
+    }
+}
+
+/// Input and output errors are unwrapped; other errors become `InvalidData` for unrecognized
+/// formats, `InvalidInput` for invalid parameters, and `Other` for the rest
+impl From<SoundError> for IOError {
+    fn from(err: SoundError) -> IOError {
+        let kind = match err {
+            SoundError::IOError(err) => return Arc::try_unwrap(err.0)
+                .unwrap_or_else(|shared| IOError::new(shared.kind(), shared.to_string())),
+            SoundError::UnrecognizedFormat => ErrorKind::InvalidData,
+            SoundError::InvalidParameter(_) => ErrorKind::InvalidInput,
+            SoundError::NoOutputAvailable | SoundError::MultiLoadError(_) => ErrorKind::Other
+        };
+        IOError::new(kind, err)
+    }
+}
+
+#[doc(hidden)]
+impl From<hound::Error> for SoundError {
+    fn from(err: hound::Error) -> SoundError {
+        match err {
+            hound::Error::IoError(err) => err.into(),
+            _ => SoundError::UnrecognizedFormat
+        }
+    }
+}
+
+/// An IO error within a `SoundError`
+///
+/// IO errors can't be compared or cloned directly, so two of these are equal if their errors are
+/// of the same kind, and clones share the same error. It dereferences to the wrapped error.
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct IoErrorWrapper(pub Arc<IOError>);
+
+impl PartialEq for IoErrorWrapper {
+    fn eq(&self, other: &IoErrorWrapper) -> bool {
+        self.0.kind() == other.0.kind()
+    }
+}
+
+impl Eq for IoErrorWrapper {}
+
+impl Deref for IoErrorWrapper {
+    type Target = IOError;
+
+    fn deref(&self) -> &IOError {
+        &self.0
     }
 }
 
 /// Stop handle
+///
+/// Dropping the handle stops the sound it controls.
 pub struct StopHandle {
     #[cfg(not(target_arch="wasm32"))]
-    sink: Sink,
+    controls: Arc<PlaybackControls>,
     #[cfg(target_arch="wasm32")]
     sound: Value,
+    #[cfg(target_arch="wasm32")]
+    events: Arc<EventBus>,
+    #[cfg(target_arch="wasm32")]
+    stop_fade: Duration
 }
 
 impl StopHandle {
     #[cfg(not(target_arch="wasm32"))]
-    fn new(sink: Sink) -> Result<StopHandle> {
-        Ok(StopHandle{sink})
+    fn new(controls: Arc<PlaybackControls>) -> Result<StopHandle> {
+        Ok(StopHandle{controls})
     }
 
     #[cfg(target_arch="wasm32")]
     fn new(sound: Value) -> Result<StopHandle> {
-        Ok(StopHandle{sound})
+        let events = Arc::new(EventBus::default());
+        let bus = events.clone();
+        let send = move |event: String| bus.send(match event.as_str() {
+            "started" => PlayEvent::Started,
+            "paused" => PlayEvent::Paused,
+            "resumed" => PlayEvent::Resumed,
+            "looped" => PlayEvent::Looped,
+            _ => PlayEvent::Finished
+        });
+        js! {
+            const snd = @{&sound};
+            const send = @{send};
+            let started = false;
+            let lastTime = 0;
+            snd.addEventListener("playing", () => {
+                send(started ? "resumed" : "started");
+                started = true;
+            });
+            snd.addEventListener("pause", () => { if (!snd.ended && !snd.__stopping) { send("paused"); } });
+            snd.addEventListener("timeupdate", () => {
+                if (snd.currentTime < lastTime) { send("looped"); }
+                lastTime = snd.currentTime;
+            });
+            snd.addEventListener("ended", () => {
+                send("finished");
+                send.drop();
+            });
+        }
+        Ok(StopHandle{sound, events, stop_fade: Duration::ZERO})
     }
 
     /// stops the sound
+    ///
+    /// A sound played with `Sound::play_fade_out` fades out before it stops.
     pub fn stop(self) -> Result<()> {
         #[cfg(not(target_arch="wasm32"))] {
-            self.sink.stop();
+            self.controls.stop();
         }
-        #[cfg(target_arch="wasm32")] js! {
-            @{&self.sound}.pause();
-            @{&self.sound}.currentTime = 0;
+        #[cfg(target_arch="wasm32")] {
+            self.end();
         }
         Ok(())
     }
+
+    // Stop the web sound and its partner, fading it out first if it was played with a fade
+    //
+    // Both `stop` and dropping the handle end up here, so a sound that is already stopping or has
+    // finished is left alone
+    #[cfg(target_arch="wasm32")]
+    fn end(&self) {
+        if self.stop_fade > Duration::ZERO {
+            js! {
+                const snd = @{&self.sound};
+                if (snd.__stopping || snd.ended) { return; }
+                const duration = @{self.stop_fade.as_secs_f64() * 1000.0};
+                const target = snd.volume;
+                const start = performance.now();
+                snd.__stopping = true;
+                const timer = setInterval(() => {
+                    const progress = Math.min(1, (performance.now() - start) / duration);
+                    snd.volume = target * (1 - snd.__fadeCurve(progress));
+                    if (progress >= 1) {
+                        clearInterval(timer);
+                        snd.pause();
+                        snd.currentTime = 0;
+                        snd.dispatchEvent(new Event("ended"));
+                    }
+                }, 20);
+            }
+            return;
+        }
+        let stopped: bool = js! {
+            const snd = @{&self.sound};
+            if (snd.__stopping || snd.ended) { return false; }
+            snd.__stopping = true;
+            snd.pause();
+            snd.currentTime = 0;
+            const partner = snd.partner;
+            if (partner && !partner.__stopping) {
+                partner.__stopping = true;
+                partner.pause();
+                partner.currentTime = 0;
+                partner.dispatchEvent(new Event("ended"));
+            }
+            return true;
+        }.try_into().unwrap_or(false);
+        if stopped {
+            self.events.send(PlayEvent::Finished);
+        }
+    }
+
+    /// Pause the sound, keeping its position so it can be resumed later
+    pub fn pause(&mut self) {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.set_paused(true);
+        }
+        #[cfg(target_arch="wasm32")] js! {
+            const snd = @{&self.sound};
+            snd.pause();
+            if (snd.partner) { snd.partner.pause(); }
+        }
+    }
+
+    /// Resume a paused sound from where it was paused
+    pub fn resume(&mut self) {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.set_paused(false);
+        }
+        #[cfg(target_arch="wasm32")] js! {
+            const snd = @{&self.sound};
+            snd.play();
+            if (snd.partner) { snd.partner.play(); }
+        }
+    }
+
+    /// Check if the sound is paused
+    pub fn is_paused(&self) -> bool {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.is_paused()
+        }
+        #[cfg(target_arch="wasm32")] {
+            js!( return @{&self.sound}.paused && !@{&self.sound}.ended; ).try_into().unwrap_or(false)
+        }
+    }
+
+    /// Count the frames of the sound that have been played since it started
+    ///
+    /// A frame is one sample for every channel. Frames are counted in the sound's own sample rate,
+    /// and keep counting up through loops; time spent paused is not counted.
+    ///
+    /// On the web, the count is worked out from the playback position at an assumed 44.1kHz, so
+    /// it is less precise and restarts when a looping sound loops.
+    pub fn elapsed_frames(&self) -> u64 {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.frames()
+        }
+        #[cfg(target_arch="wasm32")] {
+            let seconds: f64 = js!( return @{&self.sound}.currentTime; ).try_into().unwrap_or(0.0);
+            (seconds * 44100.0) as u64
+        }
+    }
+
+    /// Get how far the sound is into its current loop
+    ///
+    /// Unlike `elapsed_frames`, this goes back to zero each time a looping sound loops; for a
+    /// sound that doesn't loop, it's the time since the start. For `LoopMode::PingPong`, the trip
+    /// forwards and back again is one loop. Time spent paused is not counted. Returns None if the
+    /// sound hasn't started playing yet.
+    pub fn tell(&self) -> Option<Duration> {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.position()
+        }
+        #[cfg(target_arch="wasm32")] {
+            let seconds: Option<f64> = js! {
+                const snd = @{&self.sound};
+                if (snd.played.length === 0) { return null; }
+                return snd.duration > 0 && isFinite(snd.duration) ? snd.currentTime % snd.duration : snd.currentTime;
+            }.try_into().unwrap_or(None);
+            seconds.map(Duration::from_secs_f64)
+        }
+    }
+
+    /// Watch for events on the playing sound, like it looping or finishing
+    ///
+    /// Any number of receivers can watch the same sound, and each receives every event that
+    /// happens after it subscribes. A receiver that subscribes late still receives
+    /// `PlayEvent::Started` first if the sound has already started. The stream ends once the
+    /// sound finishes.
+    pub fn subscribe(&self) -> PlayEventReceiver {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.subscribe()
+        }
+        #[cfg(target_arch="wasm32")] {
+            self.events.subscribe()
+        }
+    }
+
+    /// Get the volume the sound is currently playing at
+    ///
+    /// This is applied on top of the volume the `Sound` had when it started playing.
+    pub fn volume(&self) -> f32 {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.volume()
+        }
+        #[cfg(target_arch="wasm32")] {
+            let volume: f64 = js!( return @{&self.sound}.volume; ).try_into().unwrap_or(1.0);
+            volume as f32
+        }
+    }
+
+    /// Change the volume of the playing sound
+    ///
+    /// This is applied on top of the volume the `Sound` had when it started playing. On the web,
+    /// the volume is limited to the range `[0, 1]`.
+    pub fn set_volume(&mut self, volume: f32) {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.set_volume(volume);
+        }
+        #[cfg(target_arch="wasm32")] {
+            let volume = f64::from(volume.max(0.0).min(1.0));
+            js! { @{&self.sound}.volume = @{volume}; }
+        }
+    }
+
+    /// Check if the sound has finished playing
+    ///
+    /// A looping sound never finishes on its own.
+    pub fn is_finished(&self) -> bool {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.is_finished()
+        }
+        #[cfg(target_arch="wasm32")] {
+            js!( return @{&self.sound}.ended; ).try_into().unwrap_or(false)
+        }
+    }
+
+    /// Get the most recent window of samples the sound has played, for drawing visualizers
+    ///
+    /// The window is the last 1024 samples that were played, interleaved by channel, before the
+    /// volume is applied. It is replaced a whole window at a time, so two calls in quick
+    /// succession usually return the same samples, and a window is occasionally skipped if it
+    /// was being read when the next one was ready. Returns None until the first window has been
+    /// played.
+    ///
+    /// On the web, the samples of the page's audio can't be read, so this always returns None.
+    pub fn waveform_snapshot(&self) -> Option<Vec<f32>> {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.snapshot()
+        }
+        #[cfg(target_arch="wasm32")] {
+            None
+        }
+    }
+
+    // The RMS amplitude of the most recently played samples, after the playback volume
+    #[cfg(not(target_arch="wasm32"))]
+    fn level(&self) -> f32 {
+        self.controls.level() * self.controls.volume()
+    }
 }
 
+impl Drop for StopHandle {
+    fn drop(&mut self) {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.controls.stop();
+        }
+        #[cfg(target_arch="wasm32")] {
+            self.end();
+        }
+    }
+}
+
+
+#[cfg(all(test, not(target_arch="wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcm_round_trip() {
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 0.25];
+        let sound = Sound::from_pcm(&samples, 44100, 2).unwrap();
+        let decoder = Decoder::new(Cursor::new(sound)).unwrap();
+        assert_eq!(decoder.channels(), 2);
+        assert_eq!(decoder.sample_rate(), 44100);
+        let decoded: Vec<f32> = decoder.convert_samples().collect();
+        assert_eq!(decoded.len(), samples.len());
+        for (decoded, original) in decoded.iter().zip(samples.iter()) {
+            assert!((decoded - original).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn async_stream() {
+        let samples = [0.0, 0.5, -0.5, 1.0];
+        let mut sound = Sound::from_pcm(&samples, 44100, 1).unwrap();
+        sound.set_volume(0.5);
+        let streamed = sound.into_async_stream().collect().wait().unwrap();
+        assert_eq!(streamed.len(), samples.len());
+        for (streamed, original) in streamed.iter().zip(samples.iter()) {
+            assert!((streamed - original * 0.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn pcm_invalid_layout() {
+        assert!(Sound::from_pcm(&[0.0; 3], 44100, 2).is_err());
+        assert!(Sound::from_pcm(&[0.0; 2], 44100, 0).is_err());
+        assert!(Sound::from_pcm(&[0.0; 2], 0, 1).is_err());
+    }
+
+    #[test]
+    fn error_equality() {
+        use ErrorKind;
+        let not_found = SoundError::from(IOError::new(ErrorKind::NotFound, "first"));
+        assert_eq!(not_found, SoundError::from(IOError::new(ErrorKind::NotFound, "second")));
+        assert_ne!(not_found, SoundError::from(IOError::from(ErrorKind::PermissionDenied)));
+        assert_ne!(not_found, SoundError::UnrecognizedFormat);
+    }
+
+    #[test]
+    fn directory() {
+        let dir = std::env::temp_dir().join(format!("quicksilver-sounds-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b.WAV"), encode_wav(&[0.0; 4], 44100, 1).unwrap()).unwrap();
+        fs::write(dir.join("a.wav"), encode_wav(&[0.0; 2], 44100, 2).unwrap()).unwrap();
+        fs::write(dir.join("notes.txt"), b"not a sound").unwrap();
+        let sounds = Sound::load_directory(&dir).wait();
+        fs::remove_dir_all(&dir).unwrap();
+        let sounds = sounds.unwrap();
+        assert_eq!(sounds.len(), 2);
+        assert_eq!(sounds[0].pcm().channels, 2);
+        assert_eq!(sounds[1].pcm().channels, 1);
+    }
+
+    #[test]
+    fn io_error_kinds() {
+        let kind = |err: SoundError| IOError::from(err).kind();
+        assert_eq!(kind(IOError::from(ErrorKind::NotFound).into()), ErrorKind::NotFound);
+        assert_eq!(kind(SoundError::UnrecognizedFormat), ErrorKind::InvalidData);
+        assert_eq!(kind(SoundError::NoOutputAvailable), ErrorKind::Other);
+        assert_eq!(kind(SoundError::InvalidParameter(String::new())), ErrorKind::InvalidInput);
+        assert_eq!(kind(SoundError::MultiLoadError(Vec::new())), ErrorKind::Other);
+    }
+
+    #[test]
+    fn boxed_errors() {
+        let boxed = |err: Box<dyn Error + Send + Sync>| SoundError::from(err);
+        let fetch = boxed("The fetch failed".into());
+        assert_eq!(fetch, SoundError::from(IOError::from(ErrorKind::Other)));
+        assert_eq!(fetch.to_string(), "The fetch failed");
+        assert_eq!(boxed(Box::new(IOError::from(ErrorKind::NotFound))), SoundError::from(IOError::from(ErrorKind::NotFound)));
+        assert_eq!(boxed(Box::new(SoundError::UnrecognizedFormat)), SoundError::UnrecognizedFormat);
+    }
+
+    #[test]
+    fn loop_count() {
+        let mut sound = Sound::from_pcm(&[0.0; 4], 44100, 1).unwrap();
+        assert_eq!(sound.loop_count(), 0);
+        sound.set_loop_count(3);
+        assert_eq!(sound.loop_count(), 3);
+    }
+
+    #[test]
+    fn repeat_passes() {
+        let mut sound = Sound::from_pcm(&[0.5, -0.5, 0.25], 44100, 1).unwrap();
+        sound.set_loop_sound(LoopMode::Repeat);
+        sound.set_loop_count(3);
+        sound.set_end_gain(-40.0);
+        let (source, controls) = sound.controlled_source(|source| source).unwrap();
+        let played: Vec<f32> = source.collect();
+        assert_eq!(played.len(), 9);
+        for pass in played.chunks(3) {
+            assert!((pass[0] - 0.5).abs() < 1e-4 && (pass[2] - 0.0025).abs() < 1e-4, "Got {:?}", played);
+        }
+        assert!(controls.is_finished());
+    }
+
+    #[test]
+    fn random_volume() {
+        let mut sound = Sound::from_pcm(&[0.0; 4], 44100, 1).unwrap();
+        sound.set_volume(0.5);
+        let mut first = sound.clone();
+        first.randomize_volume_seeded(0.1, 7);
+        let mut second = sound.clone();
+        second.randomize_volume_seeded(0.1, 7);
+        assert_eq!(first.volume(), second.volume());
+        assert!((first.volume() - 0.5).abs() <= 0.05);
+        sound.randomize_volume(0.0);
+        assert_eq!(sound.volume(), 0.5);
+        sound.randomize_pitch(2.0);
+        let speed = sound.random_speed();
+        assert!(speed > 0.89 && speed < 1.13);
+        sound.randomize_pitch(0.0);
+        assert_eq!(sound.random_speed(), 1.0);
+        sound.set_volume(1.9);
+        for _ in 0..10 {
+            sound.randomize_volume(1.0);
+            assert!((0.0..=2.0).contains(&sound.volume()));
+        }
+    }
+
+    #[test]
+    fn random_choice() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            counts[random_index(4, &mut rng).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&count| count > 900 && count < 1100), "{:?}", counts);
+        assert!(random_index(0, &mut rng).is_err());
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            counts[weighted_index(&[1.0, 0.0, 2.0], &mut rng).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[0] > 900 && counts[0] < 1100 && counts[2] > 1900 && counts[2] < 2100, "{:?}", counts);
+        assert!(weighted_index(&[], &mut rng).is_err());
+        assert!(weighted_index(&[1.0, -1.0], &mut rng).is_err());
+        assert!(weighted_index(&[0.0, 0.0], &mut rng).is_err());
+        let sounds = [Sound::from_pcm(&[0.0; 4], 44100, 1).unwrap()];
+        assert!(Sound::play_weighted_random(&sounds, &[1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn pitch() {
+        assert_eq!(semitones_to_rate(12).unwrap(), 2.0);
+        assert_eq!(semitones_to_rate(0).unwrap(), 1.0);
+        assert_eq!(semitones_to_rate(-48).unwrap(), 0.0625);
+        assert!((semitones_to_rate(-7).unwrap() - 0.6674).abs() < 1e-4);
+        assert!(semitones_to_rate(49).is_err());
+    }
+
+    #[test]
+    fn retries() {
+        let path = std::env::temp_dir().join(format!("quicksilver-retried-{}.wav", std::process::id()));
+        match Sound::load_with_retries(&path, 2, Duration::from_millis(1)).wait() {
+            Err(QuicksilverError::SoundError(SoundError::IOError(err))) => assert_eq!(err.kind(), ErrorKind::NotFound),
+            _ => panic!("A missing file should fail to load")
+        }
+        // Creating the loader doesn't wait for any of the retries
+        let started = std::time::Instant::now();
+        let mut loader = Sound::load_with_retries(&path, 3, Duration::from_secs(60));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let polled = future::poll_fn(|| loader.poll().map(futures::Async::Ready)).wait();
+        assert!(matches!(polled, Ok(futures::Async::NotReady)));
+        assert_eq!(loader.progress(), 0.0);
+        fs::write(&path, encode_wav(&[0.0; 2], 44100, 1).unwrap()).unwrap();
+        let sound = Sound::load_with_retries(&path, 2, Duration::from_millis(1)).wait();
+        fs::remove_file(&path).unwrap();
+        assert!(sound.is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn directory_errors() {
+        let dir = std::env::temp_dir().join(format!("quicksilver-broken-sounds-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.wav"), encode_wav(&[0.0; 2], 44100, 2).unwrap()).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing.ogg"), dir.join("b.ogg")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing.mp3"), dir.join("c.mp3")).unwrap();
+        let result = Sound::load_directory(&dir).wait();
+        fs::remove_dir_all(&dir).unwrap();
+        match result.unwrap_err() {
+            QuicksilverError::SoundError(SoundError::MultiLoadError(errors)) => {
+                let missing = SoundError::from(IOError::from(ErrorKind::NotFound));
+                assert_eq!(errors, vec![(dir.join("b.ogg"), missing.clone()), (dir.join("c.mp3"), missing)]);
+            }
+            error => panic!("Unexpected error {}", error)
+        }
+    }
+}
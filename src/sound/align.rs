@@ -0,0 +1,101 @@
+use crate::sound::Sound;
+use rustfft::{
+    FFTplanner,
+    num_complex::Complex,
+    num_traits::Zero
+};
+use std::time::Duration;
+
+// The normalized correlation needed to trust an alignment
+const MIN_CORRELATION: f32 = 0.5;
+
+impl Sound {
+    /// Find how far into `reference` this sound clip starts, by cross-correlation
+    ///
+    /// Every offset from zero up to `search_window` is tried, and the one where the two clips
+    /// line up best is returned. The clips are compared after mixing their channels together, so
+    /// this works for aligning two takes of the same recording, or a sound effect with the audio
+    /// track it was mixed into. At least half of the shorter clip has to overlap the other at an
+    /// offset for it to be considered.
+    ///
+    /// Returns None if the clips have different sample rates, or if they don't correlate well
+    /// enough at any offset to align them confidently. Only available on desktop with the `dsp`
+    /// feature.
+    pub fn align_to(&self, reference: &Sound, search_window: Duration) -> Option<Duration> {
+        let (pcm, reference_pcm) = (self.pcm(), reference.pcm());
+        if pcm.sample_rate != reference_pcm.sample_rate {
+            return None;
+        }
+        let sample_rate = f64::from(pcm.sample_rate);
+        let (samples, reference) = (pcm.to_mono(), reference_pcm.to_mono());
+        if samples.is_empty() || reference.is_empty() {
+            return None;
+        }
+        let min_overlap = (samples.len().min(reference.len()) / 2).max(1);
+        let max_lag = ((search_window.as_secs_f64() * sample_rate) as usize).min(reference.len() - min_overlap);
+        let products = cross_correlation(&reference, &samples, max_lag);
+        // The energy of the first samples of each clip, for normalizing the products
+        let energies = |samples: &[f32]| {
+            let mut total = 0.0;
+            let mut energies = vec![0.0];
+            energies.extend(samples.iter().map(|sample| {
+                total += f64::from(*sample) * f64::from(*sample);
+                total
+            }));
+            energies
+        };
+        let (energy, reference_energy) = (energies(&samples), energies(&reference));
+        let (lag, correlation) = products.iter()
+            .enumerate()
+            .map(|(lag, &product)| {
+                let overlap = samples.len().min(reference.len() - lag);
+                let norm = (energy[overlap] * (reference_energy[lag + overlap] - reference_energy[lag])).sqrt();
+                (lag, if norm > 0.0 { (product / norm) as f32 } else { 0.0 })
+            })
+            .fold((0, f32::MIN), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+        if correlation < MIN_CORRELATION {
+            return None;
+        }
+        Some(Duration::from_secs_f64(lag as f64 / sample_rate))
+    }
+}
+
+// The sum of the products of the signal and the reference from each lag, up to the largest one
+fn cross_correlation(reference: &[f32], signal: &[f32], max_lag: usize) -> Vec<f64> {
+    let size = (reference.len() + signal.len()).next_power_of_two();
+    let transform = |samples: &[f32]| {
+        let mut input: Vec<Complex<f32>> = samples.iter().map(|&sample| Complex::new(sample, 0.0)).collect();
+        input.resize(size, Complex::zero());
+        let mut output = vec![Complex::zero(); size];
+        FFTplanner::new(false).plan_fft(size).process(&mut input, &mut output);
+        output
+    };
+    let mut spectrum: Vec<Complex<f32>> = transform(reference).iter()
+        .zip(transform(signal).iter())
+        .map(|(reference, signal)| reference * signal.conj())
+        .collect();
+    let mut products = vec![Complex::zero(); size];
+    FFTplanner::new(true).plan_fft(size).process(&mut spectrum, &mut products);
+    products[..=max_lag].iter().map(|product| f64::from(product.re) / size as f64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::pcm::noise;
+
+    #[test]
+    fn alignment() {
+        let track = noise(8000, 1);
+        let reference = Sound::from_pcm(&track, 8000, 1).unwrap();
+        let take = Sound::from_pcm(&track[2000..6000], 8000, 1).unwrap();
+        let offset = take.align_to(&reference, Duration::from_secs(1)).unwrap();
+        assert!((offset.as_secs_f64() - 0.25).abs() < 1e-6, "Aligned at {:?}", offset);
+        assert_eq!(take.align_to(&reference, Duration::from_millis(100)), None);
+        assert_eq!(reference.align_to(&reference, Duration::from_secs(1)), Some(Duration::ZERO));
+        let unrelated = Sound::from_pcm(&noise(4000, 2), 8000, 1).unwrap();
+        assert_eq!(unrelated.align_to(&reference, Duration::from_secs(1)), None);
+        let resampled = Sound::from_pcm(&track[2000..6000], 16000, 1).unwrap();
+        assert_eq!(resampled.align_to(&reference, Duration::from_secs(1)), None);
+    }
+}
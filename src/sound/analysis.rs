@@ -0,0 +1,149 @@
+use crate::sound::{
+    Sound,
+    pcm::{amplitude_to_db, db_to_amplitude}
+};
+use std::time::Duration;
+
+// The part of the clip that detect_silence_threshold measures the noise floor from
+const NOISE_FLOOR_MS: u64 = 100;
+// The threshold returned when the noise floor can't be measured
+const FALLBACK_THRESHOLD_DB: f32 = -60.0;
+
+impl Sound {
+    /// Measure the silence at the start and the end of the sound clip
+    ///
+    /// A frame counts as silent if every channel is at or below `threshold_db`, measured relative
+    /// to full scale (so the threshold is usually negative, like -60). Returns the length of the
+    /// leading and the trailing silence, in that order. A clip that is silent throughout is
+    /// reported as being entirely leading silence.
+    pub fn analyze_silence(&self, threshold_db: f32) -> (Duration, Duration) {
+        let pcm = self.pcm();
+        let threshold = db_to_amplitude(threshold_db);
+        let is_loud = |frame: &[f32]| frame.iter().any(|sample| sample.abs() > threshold);
+        let mut frames = pcm.samples.chunks(pcm.channels as usize);
+        match frames.position(is_loud) {
+            Some(first) => {
+                let last = frames.rposition(is_loud).map_or(first, |last| first + 1 + last);
+                (pcm.duration_of(first), pcm.duration_of(pcm.frames() - last - 1))
+            }
+            None => (pcm.duration_of(pcm.frames()), Duration::ZERO)
+        }
+    }
+
+    /// Suggest a threshold for `analyze_silence`, in decibels relative to full scale
+    ///
+    /// The noise floor is measured as the root-mean-square level of the first 100 milliseconds of
+    /// the clip, which is assumed to be silence, and `headroom_db` is added to it; 6 dB is a good
+    /// place to start. If the clip is shorter than 100 milliseconds, or starts with digital
+    /// silence, -60 dB is returned instead.
+    pub fn detect_silence_threshold(&self, headroom_db: f32) -> f32 {
+        let pcm = self.pcm();
+        let frames = (NOISE_FLOOR_MS * u64::from(pcm.sample_rate) / 1000) as usize;
+        if frames == 0 || pcm.frames() < frames {
+            return FALLBACK_THRESHOLD_DB;
+        }
+        let noise = &pcm.samples[..frames * pcm.channels as usize];
+        let mean_square = noise.iter().map(|&sample| f64::from(sample * sample)).sum::<f64>() / noise.len() as f64;
+        if mean_square == 0.0 {
+            return FALLBACK_THRESHOLD_DB;
+        }
+        amplitude_to_db(mean_square.sqrt() as f32) + headroom_db
+    }
+
+    /// Measure the overall loudness of the sound clip, in decibels relative to full scale
+    ///
+    /// This is the root-mean-square of every sample across all channels, without the volume
+    /// applied, so a full-scale sine wave measures about -3 dB. The measurement is currently
+    /// unweighted; it may become A-weighted in the future. Returns negative infinity for a silent
+    /// clip.
+    pub fn rms_db(&self) -> f32 {
+        let samples = &self.pcm().samples;
+        if samples.is_empty() {
+            return f32::NEG_INFINITY;
+        }
+        let mean_square = samples.iter().map(|&sample| f64::from(sample * sample)).sum::<f64>() / samples.len() as f64;
+        amplitude_to_db(mean_square.sqrt() as f32)
+    }
+
+    /// Count how many times the signal of the sound clip crosses zero
+    ///
+    /// Each channel is counted separately and the counts are added together. A sample of exactly
+    /// zero counts as positive. The zero-crossing rate, this count divided by the duration, is a
+    /// cheap way to tell noisy or high-pitched sounds from low, tonal ones.
+    pub fn zero_cross_count(&self) -> u64 {
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        (0..channels)
+            .map(|channel| {
+                let signs: Vec<bool> = pcm.samples.iter().skip(channel).step_by(channels).map(|&sample| sample >= 0.0).collect();
+                signs.windows(2).filter(|pair| pair[0] != pair[1]).count() as u64
+            })
+            .sum()
+    }
+
+    /// Compare the loudness of this sound clip to another, in decibels
+    ///
+    /// This is the difference between their `rms_db` measurements, so a positive result means
+    /// this clip is louder. Comparing against a silent clip gives an infinite result, and
+    /// comparing two silent clips gives NaN.
+    pub fn compare_loudness(&self, other: &Sound) -> f32 {
+        self.rms_db() - other.rms_db()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_threshold() {
+        let mut samples: Vec<f32> = (0..20).map(|index| if index % 2 == 0 { 0.01 } else { -0.01 }).collect();
+        samples.extend(vec![0.5; 20]);
+        let sound = Sound::from_pcm(&samples, 100, 2).unwrap();
+        assert!((sound.detect_silence_threshold(6.0) + 34.0).abs() < 0.1);
+        assert_eq!(Sound::from_pcm(&samples[..18], 100, 2).unwrap().detect_silence_threshold(6.0), -60.0);
+        assert_eq!(Sound::from_pcm(&[0.0; 40], 100, 2).unwrap().detect_silence_threshold(6.0), -60.0);
+    }
+
+    #[test]
+    fn silence_at_both_ends() {
+        let mut samples = vec![0.0; 10];
+        samples.extend_from_slice(&[0.5, -0.5, 0.001, 0.5, 0.5]);
+        samples.extend_from_slice(&[0.0; 20]);
+        let sound = Sound::from_pcm(&samples, 100, 1).unwrap();
+        assert_eq!(sound.analyze_silence(-40.0), (Duration::from_millis(100), Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn rms() {
+        let square = Sound::from_pcm(&[1.0, -1.0, 1.0, -1.0], 100, 1).unwrap();
+        assert!(square.rms_db().abs() < 1e-3);
+        let sine: Vec<f32> = (0..4410).map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / 44100.0).sin()).collect();
+        let sine = Sound::from_pcm(&sine, 44100, 1).unwrap();
+        assert!((sine.rms_db() + 3.0103).abs() < 1e-2);
+        assert_eq!(Sound::from_pcm(&[0.0; 8], 100, 2).unwrap().rms_db(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn zero_crossings() {
+        let nyquist: Vec<f32> = (0..100).map(|i| (i as f32 * std::f32::consts::PI + std::f32::consts::FRAC_PI_2).sin()).collect();
+        assert_eq!(Sound::from_pcm(&nyquist, 100, 1).unwrap().zero_cross_count(), 99);
+        let stereo = Sound::from_pcm(&[0.5, 0.5, -0.5, 0.5, 0.5, 0.5], 100, 2).unwrap();
+        assert_eq!(stereo.zero_cross_count(), 2);
+        assert_eq!(Sound::from_pcm(&[0.0; 10], 100, 1).unwrap().zero_cross_count(), 0);
+    }
+
+    #[test]
+    fn loudness_difference() {
+        let loud = Sound::from_pcm(&[1.0, -1.0, 1.0, -1.0], 100, 1).unwrap();
+        let quiet = Sound::from_pcm(&[0.5, -0.5, 0.5, -0.5], 100, 1).unwrap();
+        assert!((loud.compare_loudness(&quiet) - 6.0206).abs() < 1e-3);
+        assert!((quiet.compare_loudness(&loud) + 6.0206).abs() < 1e-3);
+    }
+
+    #[test]
+    fn no_silence() {
+        let sound = Sound::from_pcm(&[0.5, 0.5, -0.5, -0.5], 100, 2).unwrap();
+        assert_eq!(sound.analyze_silence(-40.0), (Duration::ZERO, Duration::ZERO));
+    }
+}
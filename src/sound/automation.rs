@@ -0,0 +1,78 @@
+use crate::{
+    Result,
+    sound::SoundError
+};
+use std::time::Duration;
+
+/// A volume curve for a single playback, given by breakpoints
+///
+/// Each point is a time since the sound started playing and the volume at that time, and the
+/// volume moves linearly between neighbouring points. Before the first point and after the last,
+/// the volume stays at the level of that point. The points must be sorted by time, and the
+/// volumes can't be negative.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Envelope {
+    /// The times and volumes of the breakpoints
+    pub points: Vec<(Duration, f32)>
+}
+
+impl Envelope {
+    /// Create an envelope from its breakpoints
+    pub fn new(points: Vec<(Duration, f32)>) -> Envelope {
+        Envelope { points }
+    }
+
+    pub(super) fn validate(&self) -> Result<()> {
+        if self.points.is_empty() {
+            return Err(SoundError::InvalidParameter("The envelope needs at least one point".to_owned()).into());
+        }
+        if self.points.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err(SoundError::InvalidParameter("The envelope points must be sorted by time".to_owned()).into());
+        }
+        if let Some(&(time, volume)) = self.points.iter().find(|&&(_, volume)| volume.is_nan() || volume < 0.0) {
+            return Err(SoundError::InvalidParameter(format!("The point at {:?} has a negative volume ({})", time, volume)).into());
+        }
+        Ok(())
+    }
+
+    // The volume at a number of seconds since playback started
+    //
+    // The web follows the envelope in the browser instead
+    #[cfg_attr(target_arch="wasm32", allow(dead_code))]
+    pub(super) fn volume_at(&self, seconds: f64) -> f32 {
+        let next = self.points.partition_point(|&(time, _)| time.as_secs_f64() <= seconds);
+        match (next.checked_sub(1).map(|index| self.points[index]), self.points.get(next)) {
+            (Some((start_time, start)), Some(&(end_time, end))) => {
+                let (start_time, end_time) = (start_time.as_secs_f64(), end_time.as_secs_f64());
+                let progress = (seconds - start_time) / (end_time - start_time);
+                start + (end - start) * progress as f32
+            }
+            (Some((_, volume)), None) | (None, Some(&(_, volume))) => volume,
+            (None, None) => 1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        let ms = Duration::from_millis;
+        let envelope = Envelope::new(vec![(ms(100), 0.0), (ms(300), 1.0), (ms(300), 0.5), (ms(500), 0.25)]);
+        assert!(envelope.validate().is_ok());
+        let expected = [(0.0, 0.0), (0.1, 0.0), (0.2, 0.5), (0.3, 0.5), (0.4, 0.375), (2.0, 0.25)];
+        for &(seconds, volume) in expected.iter() {
+            assert!((envelope.volume_at(seconds) - volume).abs() < 1e-5, "The volume at {}s is {}", seconds, envelope.volume_at(seconds));
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let ms = Duration::from_millis;
+        assert!(Envelope::default().validate().is_err());
+        assert!(Envelope::new(vec![(ms(200), 1.0), (ms(100), 1.0)]).validate().is_err());
+        assert!(Envelope::new(vec![(ms(100), -0.5)]).validate().is_err());
+    }
+}
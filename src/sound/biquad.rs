@@ -0,0 +1,69 @@
+// A second-order IIR filter, in transposed direct form II
+//
+// The coefficients are normalized so that a0 is 1
+#[derive(Clone, Debug)]
+pub(super) struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64
+}
+
+impl Biquad {
+    pub(super) fn new(b: [f64; 3], a: [f64; 2]) -> Biquad {
+        Biquad {
+            b0: b[0],
+            b1: b[1],
+            b2: b[2],
+            a1: a[0],
+            a2: a[1],
+            z1: 0.0,
+            z2: 0.0
+        }
+    }
+
+    pub(super) fn process(&mut self, input: f64) -> f64 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    // Run interleaved samples through the filter, with a separate filter state for each channel
+    pub(super) fn filter_interleaved(&self, samples: &mut [f64], channels: usize) {
+        let mut filters = vec![self.clone(); channels];
+        for frame in samples.chunks_mut(channels) {
+            for (sample, filter) in frame.iter_mut().zip(filters.iter_mut()) {
+                *sample = filter.process(*sample);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impulse_response() {
+        // A two-sample moving average
+        let mut average = Biquad::new([0.5, 0.5, 0.0], [0.0, 0.0]);
+        let output: Vec<f64> = [1.0, 0.0, 0.0].iter().map(|&sample| average.process(sample)).collect();
+        assert_eq!(output, vec![0.5, 0.5, 0.0]);
+        // A leaky integrator
+        let mut integrator = Biquad::new([1.0, 0.0, 0.0], [-0.5, 0.0]);
+        let output: Vec<f64> = [1.0, 0.0, 0.0].iter().map(|&sample| integrator.process(sample)).collect();
+        assert_eq!(output, vec![1.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn separate_channels() {
+        let average = Biquad::new([0.5, 0.5, 0.0], [0.0, 0.0]);
+        let mut samples = vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        average.filter_interleaved(&mut samples, 2);
+        assert_eq!(samples, vec![0.5, 0.0, 0.5, 0.5, 0.0, 0.5]);
+    }
+}
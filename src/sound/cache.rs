@@ -0,0 +1,186 @@
+use crate::{
+    error::QuicksilverError,
+    sound::{Sound, SoundError, from_shared_bytes, sound_paths}
+};
+use futures::{Future, future};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering}
+    },
+    thread
+};
+
+/// A store of loaded sound files, so the same file isn't read or kept in memory twice
+///
+/// Sounds loaded through the same cache share their data. The cache can be shared between
+/// threads; files are keyed by the path they were loaded from, exactly as it was given.
+#[derive(Debug, Default)]
+pub struct SoundCache {
+    files: Mutex<HashMap<PathBuf, Arc<Vec<u8>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64
+}
+
+impl SoundCache {
+    /// Create an empty cache
+    pub fn new() -> SoundCache {
+        SoundCache::default()
+    }
+
+    /// Remove a file from the cache, so the next load reads it again
+    ///
+    /// Sounds already loaded from the file keep their data.
+    pub fn evict(&self, path: impl AsRef<Path>) {
+        self.lock().remove(path.as_ref());
+    }
+
+    /// Remove every file from the cache
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Create a cache with every sound file in a directory loaded into it at once
+    ///
+    /// The files are found like `Sound::load_directory` finds them, and each is loaded on its own
+    /// thread. If any of them fail, the errors for the failed files are returned together, with
+    /// their paths, as a `SoundError::MultiLoadError`. Use `preload` to keep the files that did
+    /// load.
+    pub fn preload_all(dir: impl AsRef<Path>) -> impl Future<Item = SoundCache, Error = QuicksilverError> {
+        let cache = SoundCache::new();
+        cache.preload(dir).map(|_| cache)
+    }
+
+    /// Load every sound file in a directory into this cache at once
+    ///
+    /// This is like `preload_all`, except that the files that load are kept in the cache even if
+    /// others fail.
+    pub fn preload(&self, dir: impl AsRef<Path>) -> impl Future<Item = (), Error = QuicksilverError> {
+        future::result(self.preload_files(dir.as_ref()).map_err(QuicksilverError::from))
+    }
+
+    /// Get the fraction of loads through this cache that found their file already in it
+    ///
+    /// Returns 0 if nothing has been loaded through the cache yet. Clearing or evicting files
+    /// doesn't reset the count.
+    pub fn hit_rate(&self) -> f32 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        if hits + misses == 0 {
+            0.0
+        } else {
+            hits as f32 / (hits + misses) as f32
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Arc<Vec<u8>>>> {
+        self.files.lock().expect("The sound cache lock was poisoned")
+    }
+
+    fn load(&self, path: &Path) -> Result<Sound, SoundError> {
+        let cached = self.lock().get(path).cloned();
+        if let Some(bytes) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return from_shared_bytes(bytes);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let sound = from_shared_bytes(Arc::new(fs::read(path)?))?;
+        // Another thread may have loaded the same file in the meantime
+        let stored = self.lock().entry(path.to_owned()).or_insert_with(|| sound.val.clone()).clone();
+        if Arc::ptr_eq(&stored, &sound.val) {
+            Ok(sound)
+        } else {
+            from_shared_bytes(stored)
+        }
+    }
+
+    fn preload_files(&self, dir: &Path) -> Result<(), SoundError> {
+        let paths = sound_paths(dir)?;
+        let errors: Vec<(PathBuf, SoundError)> = thread::scope(|scope| {
+            let loading: Vec<_> = paths.iter()
+                .map(|path| (path, scope.spawn(move || self.load(path))))
+                .collect();
+            loading.into_iter()
+                .filter_map(|(path, handle)| {
+                    let err = handle.join().expect("A sound loading thread panicked").err()?;
+                    Some((path.clone(), err))
+                })
+                .collect()
+        });
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SoundError::MultiLoadError(errors))
+        }
+    }
+}
+
+impl Sound {
+    /// Load a sound clip from a path, reusing the data already in the cache if it was loaded before
+    ///
+    /// A file that isn't in the cache yet is loaded like `Sound::load` and then stored. Each
+    /// returned sound has its own volume and looping settings.
+    pub fn load_with_cache(path: impl AsRef<Path>, cache: &SoundCache) -> impl Future<Item = Sound, Error = QuicksilverError> {
+        future::result(cache.load(path.as_ref()).map_err(QuicksilverError::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::pcm::encode_wav;
+
+    #[test]
+    fn shared_data() {
+        let path = std::env::temp_dir().join(format!("quicksilver-cached-{}.wav", std::process::id()));
+        fs::write(&path, encode_wav(&[0.0; 4], 44100, 1).unwrap()).unwrap();
+        let cache = SoundCache::new();
+        let first = Sound::load_with_cache(&path, &cache).wait().unwrap();
+        fs::remove_file(&path).unwrap();
+        let second = Sound::load_with_cache(&path, &cache).wait().unwrap();
+        assert!(Arc::ptr_eq(&first.val, &second.val));
+        cache.evict(&path);
+        assert!(Sound::load_with_cache(&path, &cache).wait().is_err());
+        assert!((cache.hit_rate() - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn preload() {
+        let dir = std::env::temp_dir().join(format!("quicksilver-preload-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.wav"), encode_wav(&[0.0; 4], 44100, 1).unwrap()).unwrap();
+        fs::write(dir.join("b.wav"), encode_wav(&[0.5; 4], 44100, 1).unwrap()).unwrap();
+        fs::write(dir.join("notes.txt"), b"not a sound").unwrap();
+        assert_eq!(SoundCache::new().hit_rate(), 0.0);
+        let cache = SoundCache::preload_all(&dir).wait().unwrap();
+        assert!(Sound::load_with_cache(dir.join("a.wav"), &cache).wait().is_ok());
+        assert!(Sound::load_with_cache(dir.join("b.wav"), &cache).wait().is_ok());
+        assert_eq!(cache.hit_rate(), 0.5);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(SoundCache::preload_all(&dir).wait().is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn preload_errors() {
+        let dir = std::env::temp_dir().join(format!("quicksilver-broken-preload-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.wav"), encode_wav(&[0.0; 4], 44100, 1).unwrap()).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing.ogg"), dir.join("b.ogg")).unwrap();
+        let cache = SoundCache::new();
+        let result = cache.preload(&dir).wait();
+        fs::remove_dir_all(&dir).unwrap();
+        match result.unwrap_err() {
+            QuicksilverError::SoundError(SoundError::MultiLoadError(errors)) => {
+                let missing = SoundError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+                assert_eq!(errors, vec![(dir.join("b.ogg"), missing)]);
+            }
+            error => panic!("Unexpected error {}", error)
+        }
+        // The file that loaded is still in the cache
+        assert!(Sound::load_with_cache(dir.join("a.wav"), &cache).wait().is_ok());
+    }
+}
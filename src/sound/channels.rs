@@ -0,0 +1,150 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::f32::consts::FRAC_1_SQRT_2;
+
+// The most channels a layout is defined for
+const MAX_CHANNELS: u16 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Speaker {
+    FrontLeft,
+    FrontRight,
+    Center,
+    LowFrequency,
+    BackLeft,
+    BackRight,
+    BackCenter,
+    SideLeft,
+    SideRight
+}
+
+use self::Speaker::*;
+
+// The speakers of each channel count, in the order WAV files store them
+fn layout(channels: u16) -> &'static [Speaker] {
+    match channels {
+        1 => &[Center],
+        2 => &[FrontLeft, FrontRight],
+        3 => &[FrontLeft, FrontRight, Center],
+        4 => &[FrontLeft, FrontRight, BackLeft, BackRight],
+        5 => &[FrontLeft, FrontRight, Center, BackLeft, BackRight],
+        6 => &[FrontLeft, FrontRight, Center, LowFrequency, BackLeft, BackRight],
+        7 => &[FrontLeft, FrontRight, Center, LowFrequency, BackCenter, SideLeft, SideRight],
+        _ => &[FrontLeft, FrontRight, Center, LowFrequency, BackLeft, BackRight, SideLeft, SideRight]
+    }
+}
+
+// The speakers a missing speaker is folded into, in order of preference
+//
+// The first option the layout has all the speakers of is used. If there isn't one, the last
+// option is folded down further, which always ends at the front or center speakers.
+fn fallbacks(speaker: Speaker) -> &'static [&'static [(Speaker, f32)]] {
+    match speaker {
+        FrontLeft => &[&[(Center, 0.5)]],
+        FrontRight => &[&[(Center, 0.5)]],
+        Center => &[&[(FrontLeft, FRAC_1_SQRT_2), (FrontRight, FRAC_1_SQRT_2)]],
+        // Nothing else can play the lowest frequencies properly, so they are left out
+        LowFrequency => &[],
+        BackLeft => &[&[(SideLeft, 1.0)], &[(FrontLeft, FRAC_1_SQRT_2)]],
+        BackRight => &[&[(SideRight, 1.0)], &[(FrontRight, FRAC_1_SQRT_2)]],
+        BackCenter => &[
+            &[(BackLeft, FRAC_1_SQRT_2), (BackRight, FRAC_1_SQRT_2)],
+            &[(SideLeft, FRAC_1_SQRT_2), (SideRight, FRAC_1_SQRT_2)],
+            &[(FrontLeft, 0.5), (FrontRight, 0.5)]
+        ],
+        SideLeft => &[&[(BackLeft, 1.0)], &[(FrontLeft, FRAC_1_SQRT_2)]],
+        SideRight => &[&[(BackRight, 1.0)], &[(FrontRight, FRAC_1_SQRT_2)]]
+    }
+}
+
+// The channels of a layout that a speaker is played through, with the gain for each
+fn route(speaker: Speaker, layout: &[Speaker]) -> Vec<(usize, f32)> {
+    if let Some(channel) = layout.iter().position(|&other| other == speaker) {
+        return vec![(channel, 1.0)];
+    }
+    let options = fallbacks(speaker);
+    let option = match options.iter().find(|option| option.iter().all(|(speaker, _)| layout.contains(speaker))) {
+        Some(option) => option,
+        None => match options.last() {
+            Some(option) => option,
+            None => return Vec::new()
+        }
+    };
+    option.iter()
+        .flat_map(|&(speaker, gain)| route(speaker, layout).into_iter().map(move |(channel, other)| (channel, gain * other)))
+        .collect()
+}
+
+impl Sound {
+    /// Convert the sound clip to a different number of channels, returning the converted clip
+    ///
+    /// The channels are taken to be in the standard order of WAV files: mono, stereo, stereo
+    /// with a center speaker, quadraphonic, 5.0, 5.1, 6.1 and 7.1 surround for 1 to 8 channels.
+    /// Each speaker is played on the same speaker of the new layout if it has one, and is
+    /// otherwise mixed into the nearest speakers it does have, the way surround sound is usually
+    /// downmixed. Mono sounds are played only on the center speaker when there is one, and the
+    /// low-frequency channel is dropped from layouts without one. Samples that would go past
+    /// full scale are clipped.
+    ///
+    /// Fails unless both this clip and the new one have between 1 and 8 channels. The new clip
+    /// keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn with_channels(&self, channels: u16) -> Result<Sound> {
+        let pcm = self.pcm();
+        for &count in &[pcm.channels, channels] {
+            if count == 0 || count > MAX_CHANNELS {
+                return Err(SoundError::InvalidParameter(format!(
+                    "Channel layouts are only defined for 1 to {} channels, not {}", MAX_CHANNELS, count)).into());
+            }
+        }
+        let target = layout(channels);
+        let routes: Vec<Vec<(usize, f32)>> = layout(pcm.channels).iter().map(|&speaker| route(speaker, target)).collect();
+        let mut samples = vec![0.0; pcm.frames() * channels as usize];
+        for (input, output) in pcm.samples.chunks(pcm.channels as usize).zip(samples.chunks_mut(channels as usize)) {
+            for (sample, routes) in input.iter().zip(routes.iter()) {
+                for &(channel, gain) in routes {
+                    output[channel] += sample * gain;
+                }
+            }
+        }
+        self.with_samples_in(&samples, channels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_samples(sound: &Sound, expected: &[f32]) {
+        let samples = &sound.pcm().samples;
+        assert_eq!(samples.len(), expected.len());
+        for (sample, expected) in samples.iter().zip(expected) {
+            assert!((sample - expected).abs() < 1e-3, "Got {:?} instead of {:?}", samples, expected);
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let stereo = Sound::from_pcm(&[0.5, 0.25, -0.5, 0.0], 100, 2).unwrap();
+        assert_samples(&stereo.with_channels(1).unwrap(), &[0.375, -0.25]);
+        assert_samples(&stereo.with_channels(2).unwrap(), &[0.5, 0.25, -0.5, 0.0]);
+        let mono = Sound::from_pcm(&[0.5], 100, 1).unwrap();
+        assert_samples(&mono.with_channels(6).unwrap(), &[0.0, 0.0, 0.5, 0.0, 0.0, 0.0]);
+        assert_samples(&mono.with_channels(4).unwrap(), &[0.354, 0.354, 0.0, 0.0]);
+        // 5.1 surround, with a sample on each speaker in turn
+        let surround = Sound::from_pcm(&[0.2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.2, 0.0], 100, 6).unwrap();
+        assert_samples(&surround.with_channels(2).unwrap(), &[0.2, 0.0, 0.141, 0.141, 0.0, 0.0, 0.141, 0.0]);
+        let seven = surround.with_channels(8).unwrap();
+        assert_eq!(seven.channels(), 8);
+        assert_eq!(seven.pcm().frames(), 4);
+    }
+
+    #[test]
+    fn invalid_layouts() {
+        let stereo = Sound::from_pcm(&[0.0; 4], 100, 2).unwrap();
+        assert!(stereo.with_channels(0).is_err());
+        assert!(stereo.with_channels(9).is_err());
+        assert!(Sound::from_pcm(&[0.0; 10], 100, 10).unwrap().with_channels(2).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, pcm::PcmBuffer}
+};
+use std::f64::consts::PI;
+
+// The delay of the chorus voices when the LFO is at its lowest, in milliseconds
+const BASE_DELAY_MS: f64 = 15.0;
+// The largest depth, beyond which the voices are heard as separate echoes
+const MAX_DEPTH_MS: f32 = 50.0;
+// The longest flanger delay, beyond which it sounds like a chorus
+const MAX_FLANGER_DELAY_MS: f32 = 20.0;
+
+impl Sound {
+    /// Thicken the sound clip with a chorus, returning the changed clip
+    ///
+    /// Two delayed copies of the clip are mixed with it, and their delays are swept up and down
+    /// by a low-frequency oscillator, which detunes them slightly. `rate_hz` is how many times a
+    /// second the delays sweep, between 0 and 20. `depth` is how far the delays sweep, up to 50
+    /// milliseconds. `mix` is how much of the output is the delayed copies, from 0 for none to 1
+    /// for only the copies.
+    ///
+    /// The new clip is longer than this one by the longest delay, 15 milliseconds plus the depth,
+    /// so the copies aren't cut off. It keeps the volume and looping settings of this one. Only
+    /// available on desktop.
+    pub fn apply_chorus(&self, rate_hz: f32, depth: f32, mix: f32) -> Result<Sound> {
+        check_rate(rate_hz)?;
+        if !(0.0..=MAX_DEPTH_MS).contains(&depth) {
+            return Err(SoundError::InvalidParameter(format!("The chorus depth must be between 0 and {}ms, not {}", MAX_DEPTH_MS, depth)).into());
+        }
+        check_mix(mix)?;
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let rate = f64::from(pcm.sample_rate);
+        let base = BASE_DELAY_MS * rate / 1000.0;
+        let sweep = f64::from(depth) * rate / 1000.0;
+        let frames = pcm.frames() + (base + sweep).ceil() as usize;
+        let mut samples = Vec::with_capacity(frames * channels);
+        for frame in 0..frames {
+            let phase = 2.0 * PI * f64::from(rate_hz) * frame as f64 / rate;
+            // The two voices sweep a quarter of a cycle apart
+            let first = base + sweep * 0.5 * (1.0 - phase.cos());
+            let second = base + sweep * 0.5 * (1.0 + phase.sin());
+            for channel in 0..channels {
+                let dry = delayed(&pcm, frame, channel, 0.0);
+                let wet = (delayed(&pcm, frame, channel, first) + delayed(&pcm, frame, channel, second)) * 0.5;
+                samples.push(dry * (1.0 - mix) + wet * mix);
+            }
+        }
+        self.with_samples(&samples)
+    }
+
+    /// Sweep the sound clip with a flanger, returning the changed clip
+    ///
+    /// A copy of the clip is mixed with it, delayed by between 0 and `delay_ms` milliseconds,
+    /// with the delay swept up and down `rate_hz` times a second. The shifting comb of cancelled
+    /// frequencies gives the "jet plane" sound of a flanger. The delay must be above 0 and at most
+    /// 20 milliseconds, the rate between 0 and 20, and the mix between 0 and 1.
+    ///
+    /// Unlike a chorus, the new clip is as long as this one. It keeps the volume and looping
+    /// settings of this one. Only available on desktop.
+    pub fn apply_flanger(&self, delay_ms: f32, rate_hz: f32, mix: f32) -> Result<Sound> {
+        if !(delay_ms > 0.0 && delay_ms <= MAX_FLANGER_DELAY_MS) {
+            return Err(SoundError::InvalidParameter(format!(
+                "The flanger delay must be above 0 and at most {}ms, not {}", MAX_FLANGER_DELAY_MS, delay_ms)).into());
+        }
+        check_rate(rate_hz)?;
+        check_mix(mix)?;
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let rate = f64::from(pcm.sample_rate);
+        let sweep = f64::from(delay_ms) * rate / 1000.0;
+        let mut samples = Vec::with_capacity(pcm.samples.len());
+        for frame in 0..pcm.frames() {
+            let phase = 2.0 * PI * f64::from(rate_hz) * frame as f64 / rate;
+            let delay = sweep * 0.5 * (1.0 - phase.cos());
+            for channel in 0..channels {
+                let dry = delayed(&pcm, frame, channel, 0.0);
+                samples.push(dry * (1.0 - mix) + delayed(&pcm, frame, channel, delay) * mix);
+            }
+        }
+        self.with_samples(&samples)
+    }
+
+    /// Add an echo to the sound clip, returning the changed clip
+    ///
+    /// The clip repeats every `delay_ms` milliseconds, each repeat `feedback` times as loud as
+    /// the last. The feedback is clamped to `[0, 0.99]`, so the echoes always die out. `mix` is how
+    /// much of the output is the echoes, from 0 for none to 1 for only the echoes, and the delay
+    /// must be above 0.
+    ///
+    /// The new clip is one delay longer than this one, so the first echo isn't cut off; later
+    /// echoes past that are. It keeps the volume and looping settings of this one. Only available
+    /// on desktop.
+    pub fn apply_echo(&self, delay_ms: u32, feedback: f32, mix: f32) -> Result<Sound> {
+        if delay_ms == 0 {
+            return Err(SoundError::InvalidParameter("The echo delay must be above 0".to_owned()).into());
+        }
+        check_mix(mix)?;
+        let feedback = feedback.clamp(0.0, 0.99);
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let delay = (u64::from(delay_ms) * u64::from(pcm.sample_rate) / 1000).max(1) as usize * channels;
+        let length = pcm.samples.len() + delay;
+        let dry = |index: usize| pcm.samples.get(index).cloned().unwrap_or(0.0);
+        let mut echoes = vec![0f32; length];
+        for index in delay..length {
+            echoes[index] = dry(index - delay) + feedback * echoes[index - delay];
+        }
+        let samples: Vec<f32> = echoes.iter().enumerate()
+            .map(|(index, echo)| dry(index) * (1.0 - mix) + echo * mix)
+            .collect();
+        self.with_samples(&samples)
+    }
+}
+
+fn check_rate(rate_hz: f32) -> Result<()> {
+    if rate_hz > 0.0 && rate_hz <= 20.0 {
+        Ok(())
+    } else {
+        Err(SoundError::InvalidParameter(format!("The sweep rate must be above 0 and at most 20 Hz, not {}", rate_hz)).into())
+    }
+}
+
+fn check_mix(mix: f32) -> Result<()> {
+    if (0.0..=1.0).contains(&mix) {
+        Ok(())
+    } else {
+        Err(SoundError::InvalidParameter(format!("The mix must be between 0 and 1, not {}", mix)).into())
+    }
+}
+
+// Read a sample from `delay` frames before the given frame, interpolating between the frames
+// around it, with silence outside of the clip
+fn delayed(pcm: &PcmBuffer, frame: usize, channel: usize, delay: f64) -> f32 {
+    let channels = pcm.channels as usize;
+    let sample_at = |frame: f64| {
+        if frame < 0.0 {
+            0.0
+        } else {
+            pcm.samples.get(frame as usize * channels + channel).cloned().unwrap_or(0.0)
+        }
+    };
+    let position = frame as f64 - delay;
+    let index = position.floor();
+    let fraction = (position - index) as f32;
+    sample_at(index) * (1.0 - fraction) + sample_at(index + 1.0) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn chorus() {
+        let tone: Vec<f32> = (0..2000).map(|index| 0.5 * (index as f32 * 0.05).sin()).collect();
+        let sound = Sound::from_pcm(&tone, 1000, 2).unwrap();
+        let chorus = sound.apply_chorus(1.0, 10.0, 0.5).unwrap();
+        assert_eq!(chorus.duration_or_zero(), sound.duration_or_zero() + Duration::from_millis(25));
+        let dry = sound.apply_chorus(1.0, 10.0, 0.0).unwrap();
+        assert_eq!(&dry.pcm().samples[..2000], &sound.pcm().samples[..]);
+        assert!(chorus.pcm().samples.iter().all(|sample| sample.abs() <= 0.5));
+        assert!(sound.apply_chorus(0.0, 10.0, 0.5).is_err());
+        assert!(sound.apply_chorus(1.0, 60.0, 0.5).is_err());
+        assert!(sound.apply_chorus(1.0, 10.0, 1.5).is_err());
+    }
+
+    #[test]
+    fn flanger() {
+        let tone: Vec<f32> = (0..2000).map(|index| 0.5 * (index as f32 * 0.3).sin()).collect();
+        let sound = Sound::from_pcm(&tone, 1000, 1).unwrap();
+        let flanged = sound.apply_flanger(5.0, 0.5, 0.5).unwrap();
+        assert_eq!(flanged.pcm().frames(), sound.pcm().frames());
+        assert!(flanged.pcm().samples[..].iter().zip(&sound.pcm().samples).any(|(a, b)| (a - b).abs() > 0.1));
+        assert!(sound.apply_flanger(0.0, 0.5, 0.5).is_err());
+        assert!(sound.apply_flanger(25.0, 0.5, 0.5).is_err());
+        assert!(sound.apply_flanger(5.0, 0.5, -0.5).is_err());
+    }
+
+    #[test]
+    fn echo() {
+        let mut click = vec![0.0; 100];
+        click[0] = 0.5;
+        let sound = Sound::from_pcm(&click, 100, 1).unwrap();
+        let echoed = sound.apply_echo(300, 0.5, 0.5).unwrap();
+        assert_eq!(echoed.duration_or_zero(), sound.duration_or_zero() + Duration::from_millis(300));
+        let samples = &echoed.pcm().samples;
+        for &(index, expected) in &[(0, 0.25), (30, 0.25), (60, 0.125), (90, 0.0625), (120, 0.03125), (15, 0.0)] {
+            assert!((samples[index] - expected).abs() < 1e-3, "Sample {} is {}", index, samples[index]);
+        }
+        assert!(sound.apply_echo(0, 0.5, 0.5).is_err());
+        assert!(sound.apply_echo(300, 2.0, 0.5).is_ok());
+    }
+}
@@ -0,0 +1,69 @@
+/// The shape of a fade between silence and full volume
+///
+/// The names describe how the fade moves: `EaseIn` changes the volume slowly at first and
+/// quickly at the end, `EaseOut` the other way around, and `SCurve` is slow at both ends. The
+/// same curve is used for fading in and fading out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SoundFadeCurve {
+    /// Change the volume at a constant rate
+    #[default]
+    Linear,
+    /// Start slowly and speed up
+    EaseIn,
+    /// Start quickly and slow down
+    EaseOut,
+    /// Start and end slowly, changing quickest in the middle
+    SCurve
+}
+
+impl SoundFadeCurve {
+    /// Find how far the volume has moved once a fade is `progress` of the way through
+    ///
+    /// Both the progress and the result go from 0 at the start of the fade to 1 at the end. The
+    /// progress is clamped to that range.
+    pub fn apply(self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            SoundFadeCurve::Linear => linear(progress),
+            SoundFadeCurve::EaseIn => ease_in(progress),
+            SoundFadeCurve::EaseOut => ease_out(progress),
+            SoundFadeCurve::SCurve => s_curve(progress)
+        }
+    }
+}
+
+fn linear(t: f32) -> f32 {
+    t
+}
+
+fn ease_in(t: f32) -> f32 {
+    t * t
+}
+
+fn ease_out(t: f32) -> f32 {
+    t * (2.0 - t)
+}
+
+fn s_curve(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves() {
+        let curves = [SoundFadeCurve::Linear, SoundFadeCurve::EaseIn, SoundFadeCurve::EaseOut, SoundFadeCurve::SCurve];
+        for &curve in curves.iter() {
+            assert_eq!(curve.apply(0.0), 0.0);
+            assert_eq!(curve.apply(1.0), 1.0);
+            assert_eq!(curve.apply(2.0), 1.0);
+            assert_eq!(curve.apply(-1.0), 0.0);
+        }
+        assert_eq!(SoundFadeCurve::EaseIn.apply(0.5), 0.25);
+        assert_eq!(SoundFadeCurve::EaseOut.apply(0.5), 0.75);
+        assert_eq!(SoundFadeCurve::SCurve.apply(0.5), 0.5);
+        assert!(SoundFadeCurve::SCurve.apply(0.1) < 0.1);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+
+impl Sound {
+    /// Distort the sound clip by amplifying it and clipping the peaks, returning the changed clip
+    ///
+    /// The samples are multiplied by `gain`, and then anything beyond `clip_level` either way is
+    /// cut off, which gives the harsh, buzzy sound of an overdriven speaker. The gain must be
+    /// positive, and the clip level must be above 0 and at most 1.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_distortion(&self, gain: f32, clip_level: f32) -> Result<Sound> {
+        self.distort(gain, clip_level, |sample| sample.clamp(-1.0, 1.0))
+    }
+
+    /// Distort the sound clip like `apply_distortion`, but round off the peaks instead of cutting them
+    ///
+    /// The amplified samples are saturated with a `tanh` curve that approaches `clip_level`,
+    /// which sounds warmer than hard clipping and adds fewer high harmonics.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_smooth_distortion(&self, gain: f32, clip_level: f32) -> Result<Sound> {
+        self.distort(gain, clip_level, f32::tanh)
+    }
+
+    // Amplify the samples and shape them with a curve that saturates at 1, scaled to the level
+    fn distort(&self, gain: f32, clip_level: f32, shape: impl Fn(f32) -> f32) -> Result<Sound> {
+        if !(gain > 0.0 && gain.is_finite()) {
+            return Err(SoundError::InvalidParameter(format!("The distortion gain must be positive, not {}", gain)).into());
+        }
+        if !(clip_level > 0.0 && clip_level <= 1.0) {
+            return Err(SoundError::InvalidParameter(format!("The clip level must be above 0 and at most 1, not {}", clip_level)).into());
+        }
+        let samples: Vec<f32> = self.pcm().samples.iter()
+            .map(|&sample| clip_level * shape(sample * gain / clip_level))
+            .collect();
+        self.with_samples(&samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distortion() {
+        let tone: Vec<f32> = (0..1000).map(|index| 0.8 * (index as f32 * 0.05).sin()).collect();
+        let sound = Sound::from_pcm(&tone, 1000, 1).unwrap();
+        for distorted in &[sound.apply_distortion(4.0, 0.5).unwrap(), sound.apply_smooth_distortion(4.0, 0.5).unwrap()] {
+            let samples = &distorted.pcm().samples;
+            assert!(samples.iter().all(|sample| sample.abs() <= 0.5 + 1e-4));
+            assert!(samples.iter().any(|sample| sample.abs() > 0.49));
+        }
+        let hard = sound.apply_distortion(2.0, 1.0).unwrap();
+        assert!((hard.pcm().samples[4] - tone[4] * 2.0).abs() < 1e-3);
+        assert!(sound.apply_distortion(0.0, 0.5).is_err());
+        assert!(sound.apply_distortion(2.0, 1.5).is_err());
+        assert!(sound.apply_smooth_distortion(2.0, 0.0).is_err());
+    }
+}
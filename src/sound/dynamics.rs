@@ -0,0 +1,207 @@
+use crate::{
+    Result,
+    sound::{
+        Sound, SoundError,
+        pcm::{amplitude_to_db, db_to_amplitude},
+        sidechain::gain_reduction_db
+    }
+};
+use std::collections::VecDeque;
+
+// How fast the expander follows the level of a clip
+const EXPANDER_ATTACK_MS: f32 = 1.0;
+const EXPANDER_RELEASE_MS: f32 = 50.0;
+// How far ahead the limiter looks for peaks, so it can turn the gain down smoothly before them
+const LIMITER_LOOK_AHEAD_MS: u32 = 10;
+
+impl Sound {
+    /// Lower the volume of the loud parts of the sound clip, returning the compressed clip
+    ///
+    /// Whenever the level of the clip goes above `threshold_db`, relative to full scale, it is
+    /// brought down so that it only rises `1 / ratio` as far above the threshold; a ratio of 4
+    /// turns a peak 8 decibels over the threshold into one 2 decibels over it. The attack and
+    /// release are how many milliseconds the compressor takes to react to the level rising and
+    /// falling. Fails if the ratio is below 1 or the times are negative.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_compressor(&self, threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) -> Result<Sound> {
+        if ratio.is_nan() || ratio < 1.0 {
+            return Err(SoundError::InvalidParameter(format!("A compression ratio must be at least 1, not {}", ratio)).into());
+        }
+        if !(attack_ms >= 0.0 && release_ms >= 0.0) {
+            return Err(SoundError::InvalidParameter(format!(
+                "The attack and release can't be negative, not {}ms and {}ms", attack_ms, release_ms)).into());
+        }
+        self.apply_dynamics(attack_ms, release_ms, |level_db| gain_reduction_db(level_db, threshold_db, ratio))
+    }
+
+    /// Lower the volume of the quiet parts of the sound clip, returning the expanded clip
+    ///
+    /// This is the opposite of `apply_compressor`: whenever the level of the clip falls below
+    /// `threshold_db`, it is pushed down so that it ends up `ratio` times as far below the
+    /// threshold, while everything above the threshold is left alone. Large ratios work as a noise
+    /// gate, silencing the hiss between the louder parts of a recording. Fails if the ratio is
+    /// below 1.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_expander(&self, threshold_db: f32, ratio: f32) -> Result<Sound> {
+        if ratio.is_nan() || ratio < 1.0 {
+            return Err(SoundError::InvalidParameter(format!("An expansion ratio must be at least 1, not {}", ratio)).into());
+        }
+        self.apply_dynamics(EXPANDER_ATTACK_MS, EXPANDER_RELEASE_MS, |level_db| expansion_db(level_db, threshold_db, ratio))
+    }
+
+    /// Keep every sample of the sound clip at or below `ceiling_db`, returning the limited clip
+    ///
+    /// This is a brick-wall limiter, like a compressor with an infinite ratio. It looks 10
+    /// milliseconds ahead so the gain is eased down before each peak and back up after it,
+    /// instead of clipping the peaks off. Fails unless the ceiling is at or below full scale.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_limiter(&self, ceiling_db: f32) -> Result<Sound> {
+        if !(ceiling_db <= 0.0 && ceiling_db.is_finite()) {
+            return Err(SoundError::InvalidParameter(format!("The ceiling must be at or below 0 dB, not {}", ceiling_db)).into());
+        }
+        let pcm = self.pcm();
+        let ceiling = db_to_amplitude(ceiling_db);
+        let channels = pcm.channels as usize;
+        let look_ahead = (pcm.sample_rate * LIMITER_LOOK_AHEAD_MS / 1000).max(1) as usize;
+        // The gain each frame needs on its own to stay under the ceiling
+        let needed: Vec<f32> = pcm.samples.chunks(channels)
+            .map(|frame| {
+                let peak = frame.iter().fold(0f32, |peak, sample| peak.max(sample.abs()));
+                if peak > ceiling { ceiling / peak } else { 1.0 }
+            })
+            .collect();
+        let lowest = sliding_minimum(&needed, look_ahead);
+        // Averaging the lowest gains over the look-ahead makes a smooth ramp that is still at or
+        // below the gain every peak needs, because each of the averaged windows includes the peak
+        let mut total = 0.0;
+        let mut samples = pcm.samples.clone();
+        for (index, frame) in samples.chunks_mut(channels).enumerate() {
+            total += f64::from(lowest[index]);
+            // The frames before the clip starts need no reduction
+            let outside = look_ahead.saturating_sub(index + 1);
+            if index >= look_ahead {
+                total -= f64::from(lowest[index - look_ahead]);
+            }
+            let gain = ((total + outside as f64) / look_ahead as f64) as f32;
+            for sample in frame {
+                *sample = (*sample * gain).clamp(-ceiling, ceiling);
+            }
+        }
+        self.with_samples(&samples)
+    }
+
+    // Change the gain of each frame by the reduction, in decibels, for the level of the clip
+    //
+    // The level follows the loudest channel of each frame, rising and falling at the speed of the
+    // attack and release
+    fn apply_dynamics(&self, attack_ms: f32, release_ms: f32, reduction_db: impl Fn(f32) -> f32) -> Result<Sound> {
+        let pcm = self.pcm();
+        let smoothing = |time_ms: f32| if time_ms > 0.0 {
+            1.0 - (-1000.0 / (time_ms * pcm.sample_rate as f32)).exp()
+        } else {
+            1.0
+        };
+        let (attack, release) = (smoothing(attack_ms), smoothing(release_ms));
+        let mut level = 0.0;
+        let mut samples = pcm.samples.clone();
+        for frame in samples.chunks_mut(pcm.channels as usize) {
+            let peak = frame.iter().fold(0f32, |peak, sample| peak.max(sample.abs()));
+            level += (peak - level) * if peak > level { attack } else { release };
+            let gain = db_to_amplitude(-reduction_db(amplitude_to_db(level)));
+            for sample in frame {
+                *sample *= gain;
+            }
+        }
+        self.with_samples(&samples)
+    }
+}
+
+// The downward expansion curve: how much to lower a clip at a given level
+fn expansion_db(level_db: f32, threshold_db: f32, ratio: f32) -> f32 {
+    if ratio > 1.0 && level_db < threshold_db {
+        (threshold_db - level_db) * (ratio - 1.0)
+    } else {
+        0.0
+    }
+}
+
+// The lowest of each value and the ones after it, in windows of the given length
+fn sliding_minimum(values: &[f32], window: usize) -> Vec<f32> {
+    let mut minimums = vec![0.0; values.len()];
+    // Indices of the values that could still be the minimum of a window, with increasing values
+    let mut candidates = VecDeque::new();
+    for index in (0..values.len()).rev() {
+        while candidates.back().is_some_and(|&back| values[back] >= values[index]) {
+            candidates.pop_back();
+        }
+        candidates.push_back(index);
+        if candidates.front().is_some_and(|&front| front >= index + window) {
+            candidates.pop_front();
+        }
+        minimums[index] = values[candidates[0]];
+    }
+    minimums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32) -> Vec<f32> {
+        (0..8000).map(|index| amplitude * (2.0 * std::f32::consts::PI * 200.0 * index as f32 / 8000.0).sin()).collect()
+    }
+
+    // The loudest sample in the second half of a clip, in decibels
+    fn peak_db(sound: &Sound) -> f32 {
+        amplitude_to_db(sound.pcm().samples[4000..].iter().fold(0f32, |peak, sample| peak.max(sample.abs())))
+    }
+
+    #[test]
+    fn compressor() {
+        let sound = Sound::from_pcm(&sine(1.0), 8000, 1).unwrap();
+        let compressed = sound.apply_compressor(-12.0, 4.0, 0.0, 100.0).unwrap();
+        let expected = -12.0 + (peak_db(&sound) + 12.0) / 4.0;
+        assert!((peak_db(&compressed) - expected).abs() < 0.5, "The peak is {}dB, not {}dB", peak_db(&compressed), expected);
+        let quiet = Sound::from_pcm(&sine(0.1), 8000, 1).unwrap();
+        assert_eq!(quiet.apply_compressor(-12.0, 4.0, 5.0, 100.0).unwrap().pcm(), quiet.pcm());
+        assert!(sound.apply_compressor(-12.0, 0.5, 5.0, 100.0).is_err());
+        assert!(sound.apply_compressor(-12.0, 4.0, -5.0, 100.0).is_err());
+    }
+
+    #[test]
+    fn expander() {
+        let loud = Sound::from_pcm(&sine(0.5), 8000, 1).unwrap();
+        let expanded = loud.apply_expander(-20.0, 2.0).unwrap();
+        assert_eq!(expanded.pcm().samples[4000..], loud.pcm().samples[4000..]);
+        let quiet = Sound::from_pcm(&sine(0.01), 8000, 1).unwrap();
+        let attenuation = peak_db(&quiet) - peak_db(&quiet.apply_expander(-20.0, 2.0).unwrap());
+        assert!((attenuation - 20.0).abs() < 0.5, "A -40dB sine was lowered by {}dB", attenuation);
+        let silence = Sound::from_pcm(&[0.0; 100], 8000, 1).unwrap();
+        assert_eq!(silence.apply_expander(-20.0, 1.0).unwrap().pcm(), silence.pcm());
+        assert!(loud.apply_expander(-20.0, 0.5).is_err());
+    }
+
+    #[test]
+    fn limiter() {
+        let mut samples = sine(0.25);
+        samples[6000] = 1.0;
+        let sound = Sound::from_pcm(&samples, 8000, 1).unwrap();
+        let limited = sound.apply_limiter(-6.0).unwrap();
+        assert!(amplitude_to_db(limited.peak_amplitude()) <= -6.0 + 0.1);
+        // The gain is only lowered around the peak
+        assert_eq!(limited.pcm().samples[..5000], sound.pcm().samples[..5000]);
+        assert!((limited.pcm().samples[5999] - sound.pcm().samples[5999]).abs() > 1e-3);
+        assert_eq!(limited.pcm().samples[7000..], sound.pcm().samples[7000..]);
+        assert!(sound.apply_limiter(1.0).is_err());
+        assert!(sound.apply_limiter(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn sliding_minimum() {
+        assert_eq!(super::sliding_minimum(&[3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0], 3), vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0]);
+        assert_eq!(super::sliding_minimum(&[2.0, 1.0], 5), vec![1.0, 1.0]);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::time::Duration;
+
+impl Sound {
+    /// Cut the sound clip in two at `position`, returning the part before and the part after
+    ///
+    /// The position is rounded to the nearest frame. Fails unless both parts would be at least
+    /// one frame long.
+    ///
+    /// Both new clips keep the volume and looping settings of this one. Only available on desktop.
+    pub fn split_at(&self, position: Duration) -> Result<(Sound, Sound)> {
+        let pcm = self.pcm();
+        let frame = (position.as_secs_f64() * f64::from(pcm.sample_rate)).round() as usize;
+        if frame == 0 || frame >= pcm.frames() {
+            return Err(SoundError::InvalidParameter(format!(
+                "The position ({:?}) must be inside the sound ({:?})", position, pcm.duration_of(pcm.frames()))).into());
+        }
+        let (before, after) = pcm.samples.split_at(frame * pcm.channels as usize);
+        Ok((self.with_samples(before)?, self.with_samples(after)?))
+    }
+
+    /// Get the sound clip played backwards
+    ///
+    /// The frames are reversed, so the channels stay in place. The new clip keeps the volume and
+    /// looping settings of this one. Only available on desktop.
+    pub fn reverse(&self) -> Sound {
+        let pcm = self.pcm();
+        let samples: Vec<f32> = pcm.samples.chunks(pcm.channels as usize).rev().flatten().cloned().collect();
+        self.with_samples(&samples).expect("The format came from a valid sound")
+    }
+
+    /// Combine two mono sound clips into one stereo clip, with `left` in the left channel
+    ///
+    /// If one clip is shorter, its channel is silent after it ends. The volumes of the clips are
+    /// not applied. Fails if the clips have different sample rates, or if either one isn't mono.
+    ///
+    /// Only available on desktop.
+    pub fn create_spatial_pair(left: Sound, right: Sound) -> Result<Sound> {
+        let (left, right) = (left.pcm(), right.pcm());
+        if left.channels != 1 || right.channels != 1 {
+            return Err(SoundError::InvalidParameter(format!(
+                "Both sounds must be mono, not {} and {} channels", left.channels, right.channels)).into());
+        }
+        if left.sample_rate != right.sample_rate {
+            return Err(SoundError::InvalidParameter(format!(
+                "Both sounds must have the same sample rate, not {} and {}", left.sample_rate, right.sample_rate)).into());
+        }
+        let frames = left.samples.len().max(right.samples.len());
+        let samples: Vec<f32> = (0..frames)
+            .flat_map(|frame| {
+                let sample = |samples: &[f32]| samples.get(frame).cloned().unwrap_or(0.0);
+                vec![sample(&left.samples), sample(&right.samples)]
+            })
+            .collect();
+        Sound::from_pcm(&samples, left.sample_rate, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split() {
+        let sound = Sound::from_pcm(&[0.0, 0.0, 0.5, 0.5, 0.5, 0.5], 100, 2).unwrap();
+        let (before, after) = sound.split_at(Duration::from_millis(10)).unwrap();
+        assert_eq!(before.duration_or_zero() + after.duration_or_zero(), sound.duration_or_zero());
+        assert_eq!(before.pcm().samples, vec![0.0, 0.0]);
+        assert_eq!(after.pcm().channels, 2);
+        assert!(sound.split_at(Duration::ZERO).is_err());
+        assert!(sound.split_at(Duration::from_millis(4)).is_err());
+        assert!(sound.split_at(Duration::from_millis(30)).is_err());
+    }
+
+    #[test]
+    fn reverse() {
+        let sound = Sound::from_pcm(&[0.25, -0.25, 0.5, -0.5, 1.0, 0.0], 100, 2).unwrap();
+        let reversed = sound.reverse();
+        for (sample, expected) in reversed.pcm().samples.iter().zip(&[1.0, 0.0, 0.5, -0.5, 0.25, -0.25]) {
+            assert!((sample - expected).abs() < 1e-4);
+        }
+        assert_eq!(reversed.reverse().as_ref(), sound.as_ref());
+    }
+
+    #[test]
+    fn spatial_pair() {
+        let left = Sound::from_pcm(&[0.5, -0.5, 0.25], 100, 1).unwrap();
+        let right = Sound::from_pcm(&[1.0, 0.0], 100, 1).unwrap();
+        let pair = Sound::create_spatial_pair(left.clone(), right.clone()).unwrap();
+        assert_eq!(pair.channels(), 2);
+        let expected = [0.5, 1.0, -0.5, 0.0, 0.25, 0.0];
+        assert_eq!(pair.pcm().samples.len(), expected.len());
+        for (sample, expected) in pair.pcm().samples.iter().zip(&expected) {
+            assert!((sample - expected).abs() < 1e-4);
+        }
+        assert!(Sound::create_spatial_pair(left.clone(), Sound::from_pcm(&[0.0], 200, 1).unwrap()).is_err());
+        assert!(Sound::create_spatial_pair(pair, right).is_err());
+    }
+}
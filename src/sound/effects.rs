@@ -0,0 +1,307 @@
+use crate::sound::biquad::Biquad;
+use rodio::source::Source;
+use std::{
+    f64::consts::PI,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+
+// The number of frames the effects process at a time
+const BLOCK_FRAMES: usize = 512;
+
+/// An effect that processes the mixed output of an `AudioGraph`
+///
+/// The samples are interleaved by channel and the graph always mixes in stereo, so even samples
+/// are the left channel and odd samples are the right. Each call continues from the end of the
+/// previous one, so effects with memory, like filters and reverb, should keep their state between
+/// calls.
+pub trait AudioEffect: Send {
+    /// Process a block of samples in place
+    fn process(&mut self, samples: &mut [f32], sample_rate: u32);
+}
+
+/// Multiplies the volume of the mix by a constant factor
+#[derive(Clone, Debug)]
+pub struct GainEffect {
+    gain: f32
+}
+
+impl GainEffect {
+    /// Create an effect that multiplies every sample by `gain`
+    pub fn new(gain: f32) -> GainEffect {
+        GainEffect { gain }
+    }
+}
+
+impl AudioEffect for GainEffect {
+    fn process(&mut self, samples: &mut [f32], _sample_rate: u32) {
+        for sample in samples.iter_mut() {
+            *sample *= self.gain;
+        }
+    }
+}
+
+/// Removes the frequencies of the mix above a cutoff, muffling it
+///
+/// This is a second-order Butterworth filter, which lowers frequencies by 12 decibels for every
+/// octave above the cutoff.
+#[derive(Clone, Debug)]
+pub struct LowPassEffect {
+    cutoff: f32,
+    // The filter for each channel, with the sample rate it was designed for
+    filters: Option<(u32, [Biquad; 2])>
+}
+
+impl LowPassEffect {
+    /// Create a filter that passes the frequencies below `cutoff` hertz
+    pub fn new(cutoff: f32) -> LowPassEffect {
+        LowPassEffect {
+            cutoff,
+            filters: None
+        }
+    }
+}
+
+impl AudioEffect for LowPassEffect {
+    fn process(&mut self, samples: &mut [f32], sample_rate: u32) {
+        if self.filters.as_ref().is_none_or(|(rate, _)| *rate != sample_rate) {
+            let filter = low_pass(f64::from(self.cutoff), f64::from(sample_rate));
+            self.filters = Some((sample_rate, [filter.clone(), filter]));
+        }
+        let (_, filters) = self.filters.as_mut().expect("The filters were just created");
+        for frame in samples.chunks_mut(2) {
+            for (sample, filter) in frame.iter_mut().zip(filters.iter_mut()) {
+                *sample = filter.process(f64::from(*sample)) as f32;
+            }
+        }
+    }
+}
+
+// A Butterworth low-pass filter, from the Audio EQ Cookbook
+fn low_pass(cutoff: f64, sample_rate: f64) -> Biquad {
+    let omega = 2.0 * PI * (cutoff / sample_rate).min(0.499);
+    let alpha = omega.sin() / 2f64.sqrt();
+    let a0 = 1.0 + alpha;
+    let b = (1.0 - omega.cos()) / 2.0 / a0;
+    Biquad::new([b, 2.0 * b, b], [-2.0 * omega.cos() / a0, (1.0 - alpha) / a0])
+}
+
+// The delays of the comb and all-pass filters at 44.1kHz, from Freeverb
+const COMB_DELAYS: [usize; 4] = [1557, 1617, 1491, 1422];
+const ALL_PASS_DELAYS: [usize; 2] = [556, 225];
+// The right channel's delays are slightly longer, so the reverb sounds wider
+const STEREO_SPREAD: usize = 23;
+
+/// Adds the echoes of a room to the mix
+///
+/// This is a Schroeder reverb: a set of parallel feedback delays followed by all-pass filters
+/// that smear out the echoes.
+#[derive(Clone, Debug)]
+pub struct ReverbEffect {
+    decay: f32,
+    mix: f32,
+    // The delay lines for each channel, with the sample rate they were made for
+    channels: Option<(u32, [Reverb; 2])>
+}
+
+impl ReverbEffect {
+    /// Create a reverb with the given decay and mix, both between 0 and 1
+    ///
+    /// A larger decay makes the echoes last longer, like a larger room. The mix is how much of
+    /// the output is reverb, from 0 for none to 1 for only the echoes. Values outside of the
+    /// range are clamped.
+    pub fn new(decay: f32, mix: f32) -> ReverbEffect {
+        ReverbEffect {
+            // Feedback of 1 or more would ring forever
+            decay: decay.clamp(0.0, 1.0) * 0.28 + 0.7,
+            mix: mix.clamp(0.0, 1.0),
+            channels: None
+        }
+    }
+}
+
+impl AudioEffect for ReverbEffect {
+    fn process(&mut self, samples: &mut [f32], sample_rate: u32) {
+        if self.channels.as_ref().is_none_or(|(rate, _)| *rate != sample_rate) {
+            let scale = f64::from(sample_rate) / 44100.0;
+            self.channels = Some((sample_rate, [Reverb::new(scale, 0), Reverb::new(scale, STEREO_SPREAD)]));
+        }
+        let (_, channels) = self.channels.as_mut().expect("The delay lines were just created");
+        for frame in samples.chunks_mut(2) {
+            for (sample, reverb) in frame.iter_mut().zip(channels.iter_mut()) {
+                let wet = reverb.process(*sample, self.decay);
+                *sample = *sample * (1.0 - self.mix) + wet * self.mix;
+            }
+        }
+    }
+}
+
+// The delay lines of the reverb for a single channel
+#[derive(Clone, Debug)]
+struct Reverb {
+    combs: Vec<DelayLine>,
+    all_passes: Vec<DelayLine>
+}
+
+impl Reverb {
+    fn new(scale: f64, spread: usize) -> Reverb {
+        let line = |delay: usize| DelayLine::new((((delay + spread) as f64 * scale) as usize).max(1));
+        Reverb {
+            combs: COMB_DELAYS.iter().cloned().map(line).collect(),
+            all_passes: ALL_PASS_DELAYS.iter().cloned().map(line).collect()
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32) -> f32 {
+        let mut output = 0.0;
+        for comb in self.combs.iter_mut() {
+            let delayed = comb.read();
+            comb.write(input + delayed * feedback);
+            output += delayed;
+        }
+        output /= self.combs.len() as f32;
+        for all_pass in self.all_passes.iter_mut() {
+            let delayed = all_pass.read();
+            all_pass.write(output + delayed * 0.5);
+            output = delayed - output * 0.5;
+        }
+        output
+    }
+}
+
+// A fixed delay, as a ring buffer
+#[derive(Clone, Debug)]
+struct DelayLine {
+    buffer: Vec<f32>,
+    position: usize
+}
+
+impl DelayLine {
+    fn new(length: usize) -> DelayLine {
+        DelayLine {
+            buffer: vec![0.0; length],
+            position: 0
+        }
+    }
+
+    fn read(&self) -> f32 {
+        self.buffer[self.position]
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.position] = sample;
+        self.position = (self.position + 1) % self.buffer.len();
+    }
+}
+
+// The effects of a graph, shared by its clones and every playback of it
+#[derive(Clone, Default)]
+pub(super) struct EffectChain(Arc<Mutex<Vec<Box<dyn AudioEffect>>>>);
+
+impl EffectChain {
+    pub(super) fn set(&self, effects: Vec<Box<dyn AudioEffect>>) {
+        *self.0.lock().expect("The effect chain lock was poisoned") = effects;
+    }
+
+    // Run the output of a source through the effects
+    pub(super) fn apply<S: Source<Item = f32>>(&self, source: S) -> EffectSource<S> {
+        EffectSource {
+            inner: source,
+            chain: self.clone(),
+            block: Vec::new(),
+            position: 0
+        }
+    }
+}
+
+impl fmt::Debug for EffectChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let effects = self.0.lock().expect("The effect chain lock was poisoned").len();
+        f.debug_struct("EffectChain").field("effects", &effects).finish()
+    }
+}
+
+// Processes a source through an effect chain a block at a time
+pub(super) struct EffectSource<S> {
+    inner: S,
+    chain: EffectChain,
+    block: Vec<f32>,
+    position: usize
+}
+
+impl<S: Source<Item = f32>> Iterator for EffectSource<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.block.len() {
+            let length = BLOCK_FRAMES * self.inner.channels() as usize;
+            self.block.clear();
+            self.block.extend(self.inner.by_ref().take(length));
+            self.position = 0;
+            let sample_rate = self.inner.sample_rate();
+            for effect in self.chain.0.lock().expect("The effect chain lock was poisoned").iter_mut() {
+                effect.process(&mut self.block, sample_rate);
+            }
+        }
+        let sample = self.block.get(self.position).cloned();
+        self.position += 1;
+        sample
+    }
+}
+
+impl<S: Source<Item = f32>> Source for EffectSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn chain_order() {
+        let chain = EffectChain::default();
+        chain.set(vec![Box::new(GainEffect::new(0.5)), Box::new(GainEffect::new(-2.0))]);
+        let source = SamplesBuffer::new(2, 44100, vec![0.5; 2000]);
+        let output: Vec<f32> = chain.apply(source).collect();
+        assert_eq!(output, vec![-0.5; 2000]);
+    }
+
+    #[test]
+    fn low_pass_filter() {
+        let mut filter = LowPassEffect::new(1000.0);
+        let mut constant = vec![1.0; 2000];
+        filter.process(&mut constant, 44100);
+        assert!((constant[1998] - 1.0).abs() < 1e-3 && (constant[1999] - 1.0).abs() < 1e-3);
+        // Alternating samples in each channel are at the highest frequency there is
+        let mut high: Vec<f32> = (0..2000).map(|index| if index / 2 % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        filter.process(&mut high, 44100);
+        assert!(high[1000..].iter().all(|sample| sample.abs() < 0.01));
+    }
+
+    #[test]
+    fn reverb_tail() {
+        let mut reverb = ReverbEffect::new(0.5, 1.0);
+        let mut samples = vec![0.0; 44100];
+        samples[0] = 1.0;
+        reverb.process(&mut samples, 44100);
+        assert_eq!(samples[0], 0.0);
+        assert!(samples[4000..].iter().any(|&sample| sample != 0.0));
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::time::Duration;
+
+impl Sound {
+    /// Shape the volume of the sound clip with an envelope, returning the shaped clip
+    ///
+    /// The volume ramps up from silence to `sustain_level` over the attack, holds for the
+    /// sustain, and then ramps back down to silence over the decay. Anything after the decay is
+    /// silent. The sustain level must be within `[0, 1]`, and the three stages together can't be
+    /// longer than the clip.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_envelope(&self, attack: Duration, sustain: Duration, decay: Duration, sustain_level: f32) -> Result<Sound> {
+        if !(0.0..=1.0).contains(&sustain_level) {
+            return Err(SoundError::InvalidParameter(format!("The sustain level must be between 0 and 1, not {}", sustain_level)).into());
+        }
+        let pcm = self.pcm();
+        let frames_in = |duration: Duration| (duration.as_secs_f64() * f64::from(pcm.sample_rate)).round() as usize;
+        let (attack, sustain, decay) = (frames_in(attack), frames_in(sustain), frames_in(decay));
+        if attack + sustain + decay > pcm.frames() {
+            return Err(SoundError::InvalidParameter("The envelope is longer than the sound".to_owned()).into());
+        }
+        let mut samples = pcm.samples.clone();
+        for (frame, samples) in samples.chunks_mut(pcm.channels as usize).enumerate() {
+            let gain = sustain_level * envelope_gain(frame, attack, sustain, decay);
+            for sample in samples {
+                *sample *= gain;
+            }
+        }
+        self.with_samples(&samples)
+    }
+
+    /// Change the volume of the sound clip over time, returning the changed clip
+    ///
+    /// Each point is a time and the volume at that time, and the volume moves linearly between
+    /// neighbouring points. Before the first point and after the last, the volume stays at the
+    /// level of that point. The points must be sorted by time and lie within the clip, and the
+    /// volumes can't be negative. Samples that would go past full scale are clipped.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn volume_automation(&self, points: &[(Duration, f32)]) -> Result<Sound> {
+        let pcm = self.pcm();
+        let duration = pcm.duration_of(pcm.frames());
+        if points.is_empty() {
+            return Err(SoundError::InvalidParameter("The automation needs at least one point".to_owned()).into());
+        }
+        if points.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err(SoundError::InvalidParameter("The automation points must be sorted by time".to_owned()).into());
+        }
+        if let Some(&(time, volume)) = points.iter().find(|&&(time, volume)| time > duration || volume.is_nan() || volume < 0.0) {
+            return Err(SoundError::InvalidParameter(format!(
+                "The point at {:?} with volume {} isn't a non-negative volume within the sound ({:?})", time, volume, duration)).into());
+        }
+        let frame_of = |time: Duration| time.as_secs_f64() * f64::from(pcm.sample_rate);
+        let mut segment = 0;
+        let mut samples = pcm.samples.clone();
+        for (frame, samples) in samples.chunks_mut(pcm.channels as usize).enumerate() {
+            let frame = frame as f64;
+            while segment + 1 < points.len() && frame_of(points[segment + 1].0) <= frame {
+                segment += 1;
+            }
+            let (start_time, start) = points[segment];
+            let gain = match points.get(segment + 1) {
+                Some(&(end_time, end)) if frame >= frame_of(start_time) => {
+                    let progress = (frame - frame_of(start_time)) / (frame_of(end_time) - frame_of(start_time));
+                    start + (end - start) * progress as f32
+                }
+                _ => start
+            };
+            for sample in samples {
+                *sample = (*sample * gain).clamp(-1.0, 1.0);
+            }
+        }
+        self.with_samples(&samples)
+    }
+}
+
+// The envelope at the given frame, relative to the sustain level
+fn envelope_gain(frame: usize, attack: usize, sustain: usize, decay: usize) -> f32 {
+    if frame < attack {
+        frame as f32 / attack as f32
+    } else if frame < attack + sustain {
+        1.0
+    } else if frame < attack + sustain + decay {
+        (attack + sustain + decay - frame) as f32 / (decay + 1) as f32
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tone_envelope() {
+        let tone: Vec<f32> = (0..1000).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let sound = Sound::from_pcm(&tone, 1000, 1).unwrap();
+        let ms = Duration::from_millis;
+        let shaped = sound.apply_envelope(ms(100), ms(700), ms(200), 0.5).unwrap();
+        let pcm = shaped.pcm();
+        assert_eq!(pcm.samples.len(), tone.len());
+        assert!(pcm.samples[0].abs() < 1e-3);
+        assert!(pcm.samples[999].abs() < 1e-2);
+        assert!((pcm.samples[500] - 0.5).abs() < 1e-3);
+        assert!((pcm.samples[50] - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn invalid_envelope() {
+        let sound = Sound::from_pcm(&[0.0; 100], 100, 1).unwrap();
+        let ms = Duration::from_millis;
+        assert!(sound.apply_envelope(ms(500), ms(500), ms(10), 1.0).is_err());
+        assert!(sound.apply_envelope(ms(100), ms(100), ms(100), 1.5).is_err());
+        assert!(sound.apply_envelope(ms(100), ms(100), ms(100), -0.5).is_err());
+    }
+
+    #[test]
+    fn automation() {
+        let sound = Sound::from_pcm(&[0.5; 200], 100, 2).unwrap();
+        let ms = Duration::from_millis;
+        let automated = sound.volume_automation(&[(ms(100), 0.0), (ms(500), 2.0), (ms(500), 1.0)]).unwrap();
+        let pcm = automated.pcm();
+        let expected = [(0, 0.0), (19, 0.0), (61, 0.5), (98, 0.975), (120, 0.5), (199, 0.5)];
+        for &(index, expected) in expected.iter() {
+            assert!((pcm.samples[index] - expected).abs() < 1e-3, "Sample {} is {}", index, pcm.samples[index]);
+        }
+        assert!(sound.volume_automation(&[]).is_err());
+        assert!(sound.volume_automation(&[(ms(200), 1.0), (ms(100), 1.0)]).is_err());
+        assert!(sound.volume_automation(&[(ms(1100), 1.0)]).is_err());
+        assert!(sound.volume_automation(&[(ms(100), -1.0)]).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use futures::{Async, Poll, Stream, task::AtomicTask};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak}
+};
+
+/// Something that happened to a playing sound
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlayEvent {
+    /// The sound started playing
+    Started,
+    /// The sound was paused with `StopHandle::pause`
+    Paused,
+    /// The sound was resumed with `StopHandle::resume`
+    Resumed,
+    /// A looping sound went back to its beginning
+    Looped,
+    /// The sound reached its end or was stopped
+    Finished
+}
+
+// The events waiting to be taken by a single receiver
+#[derive(Debug, Default)]
+struct EventQueue {
+    events: Mutex<VecDeque<PlayEvent>>,
+    task: AtomicTask
+}
+
+/// A stream of the events of a playing sound, created by `StopHandle::subscribe`
+///
+/// The stream ends after it yields `PlayEvent::Finished`.
+#[derive(Debug)]
+pub struct PlayEventReceiver {
+    queue: Arc<EventQueue>,
+    finished: bool
+}
+
+impl Stream for PlayEventReceiver {
+    type Item = PlayEvent;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<PlayEvent>, ()> {
+        if self.finished {
+            return Ok(Async::Ready(None));
+        }
+        self.queue.task.register();
+        match self.queue.events.lock().expect("The event queue lock was poisoned").pop_front() {
+            Some(event) => {
+                self.finished = event == PlayEvent::Finished;
+                Ok(Async::Ready(Some(event)))
+            }
+            None => Ok(Async::NotReady)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BusState {
+    queues: Vec<Weak<EventQueue>>,
+    started: bool,
+    finished: bool
+}
+
+// Sends the events of one playing sound to every receiver subscribed to it
+#[derive(Debug, Default)]
+pub(super) struct EventBus {
+    state: Mutex<BusState>
+}
+
+impl EventBus {
+    pub(super) fn send(&self, event: PlayEvent) {
+        let mut state = self.state.lock().expect("The event bus lock was poisoned");
+        if state.finished {
+            return;
+        }
+        match event {
+            PlayEvent::Started => state.started = true,
+            PlayEvent::Finished => state.finished = true,
+            _ => ()
+        }
+        state.queues.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                queue.events.lock().expect("The event queue lock was poisoned").push_back(event);
+                queue.task.notify();
+                true
+            }
+            None => false
+        });
+    }
+
+    // A receiver that subscribes late is first told if the sound already started or finished
+    pub(super) fn subscribe(&self) -> PlayEventReceiver {
+        let mut state = self.state.lock().expect("The event bus lock was poisoned");
+        let queue = Arc::new(EventQueue::default());
+        {
+            let mut events = queue.events.lock().expect("The event queue lock was poisoned");
+            if state.started {
+                events.push_back(PlayEvent::Started);
+            }
+            if state.finished {
+                events.push_back(PlayEvent::Finished);
+            }
+        }
+        state.queues.push(Arc::downgrade(&queue));
+        PlayEventReceiver {
+            queue,
+            finished: false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+
+    #[test]
+    fn late_subscribers() {
+        let bus = EventBus::default();
+        let early = bus.subscribe();
+        bus.send(PlayEvent::Started);
+        let late = bus.subscribe();
+        bus.send(PlayEvent::Looped);
+        bus.send(PlayEvent::Finished);
+        bus.send(PlayEvent::Looped);
+        let last = bus.subscribe();
+        let all = vec![PlayEvent::Started, PlayEvent::Looped, PlayEvent::Finished];
+        assert_eq!(early.collect().wait().unwrap(), all);
+        assert_eq!(late.collect().wait().unwrap(), all);
+        assert_eq!(last.collect().wait().unwrap(), vec![PlayEvent::Started, PlayEvent::Finished]);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, biquad::Biquad}
+};
+use std::f64::consts::PI;
+
+impl Sound {
+    /// Remove the frequencies below a cutoff, returning the filtered clip
+    ///
+    /// This is a second-order Butterworth filter, which lowers frequencies by 12 decibels for
+    /// every octave below the cutoff. It is useful for taking rumble and hum out of recordings.
+    /// Fails unless the cutoff is above 0 and below half the sample rate.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_high_pass(&self, cutoff_hz: f32) -> Result<Sound> {
+        let sample_rate = f64::from(self.pcm().sample_rate);
+        let nyquist = sample_rate / 2.0;
+        if !(cutoff_hz > 0.0 && f64::from(cutoff_hz) < nyquist) {
+            return Err(SoundError::InvalidParameter(format!(
+                "The cutoff must be above 0 and below {}Hz, not {}Hz", nyquist, cutoff_hz)).into());
+        }
+        self.filter(&[high_pass(f64::from(cutoff_hz), sample_rate)])
+    }
+
+    /// Keep only the frequencies in a band, returning the filtered clip
+    ///
+    /// The band is centered on `center_hz`, and `bandwidth_hz` is how wide it is between the
+    /// frequencies that are lowered by 3 decibels. Narrow bands around 1 to 2 kHz sound like a
+    /// telephone or a walkie-talkie. Fails unless the whole band is above 0 and below half the
+    /// sample rate.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_band_pass(&self, center_hz: f32, bandwidth_hz: f32) -> Result<Sound> {
+        let sample_rate = f64::from(self.pcm().sample_rate);
+        check_band(center_hz, bandwidth_hz, sample_rate)?;
+        self.filter(&[band_pass(f64::from(center_hz), f64::from(bandwidth_hz), sample_rate)])
+    }
+
+    /// Remove the frequencies in a narrow band, returning the filtered clip
+    ///
+    /// This is the opposite of `apply_band_pass`: the frequencies around `center_hz` are cut,
+    /// and `bandwidth_hz` is how wide the cut is between the frequencies that are lowered by 3
+    /// decibels. A narrow notch at 50 or 60 Hz takes mains hum out of a recording. Fails unless
+    /// the whole band is above 0 and below half the sample rate.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_notch(&self, center_hz: f32, bandwidth_hz: f32) -> Result<Sound> {
+        let sample_rate = f64::from(self.pcm().sample_rate);
+        check_band(center_hz, bandwidth_hz, sample_rate)?;
+        self.filter(&[notch(f64::from(center_hz), f64::from(bandwidth_hz), sample_rate)])
+    }
+
+    /// Boost or cut the frequencies in a set of bands, returning the equalized clip
+    ///
+    /// Each band is its center frequency in hertz, its gain in decibels, and how wide it is in
+    /// hertz; a positive gain boosts the band and a negative one cuts it, by about half as much
+    /// at the edges of its width. The bands are applied one after another, so bands that
+    /// overlap add up. Fails unless every band is within the range `apply_band_pass` accepts
+    /// and has a finite gain.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_equalizer(&self, bands: &[(f32, f32, f32)]) -> Result<Sound> {
+        let sample_rate = f64::from(self.pcm().sample_rate);
+        let mut filters = Vec::with_capacity(bands.len());
+        for &(center_hz, gain_db, bandwidth_hz) in bands {
+            check_band(center_hz, bandwidth_hz, sample_rate)?;
+            if !gain_db.is_finite() {
+                return Err(SoundError::InvalidParameter(format!("The gain of the band at {}Hz must be finite, not {}", center_hz, gain_db)).into());
+            }
+            filters.push(peaking(f64::from(center_hz), f64::from(gain_db), f64::from(bandwidth_hz), sample_rate));
+        }
+        self.filter(&filters)
+    }
+
+    // Run the clip through each filter in turn
+    fn filter(&self, filters: &[Biquad]) -> Result<Sound> {
+        let pcm = self.pcm();
+        let mut samples: Vec<f64> = pcm.samples.iter().map(|&sample| f64::from(sample)).collect();
+        for filter in filters {
+            filter.filter_interleaved(&mut samples, pcm.channels as usize);
+        }
+        let samples: Vec<f32> = samples.into_iter().map(|sample| sample as f32).collect();
+        self.with_samples(&samples)
+    }
+}
+
+// A Butterworth high-pass filter, from the Audio EQ Cookbook
+fn high_pass(cutoff: f64, sample_rate: f64) -> Biquad {
+    let omega = 2.0 * PI * cutoff / sample_rate;
+    let alpha = omega.sin() / 2f64.sqrt();
+    let a0 = 1.0 + alpha;
+    let b = (1.0 + omega.cos()) / 2.0 / a0;
+    Biquad::new([b, -2.0 * b, b], [-2.0 * omega.cos() / a0, (1.0 - alpha) / a0])
+}
+
+// A band-pass filter with a peak gain of 0dB, from the Audio EQ Cookbook
+fn band_pass(center: f64, bandwidth: f64, sample_rate: f64) -> Biquad {
+    let omega = 2.0 * PI * center / sample_rate;
+    let alpha = omega.sin() * bandwidth / center / 2.0;
+    let a0 = 1.0 + alpha;
+    Biquad::new([alpha / a0, 0.0, -alpha / a0], [-2.0 * omega.cos() / a0, (1.0 - alpha) / a0])
+}
+
+// A notch filter, from the Audio EQ Cookbook
+fn notch(center: f64, bandwidth: f64, sample_rate: f64) -> Biquad {
+    let omega = 2.0 * PI * center / sample_rate;
+    let alpha = omega.sin() * bandwidth / center / 2.0;
+    let a0 = 1.0 + alpha;
+    let a1 = -2.0 * omega.cos() / a0;
+    Biquad::new([1.0 / a0, a1, 1.0 / a0], [a1, (1.0 - alpha) / a0])
+}
+
+// A peaking filter, from the Audio EQ Cookbook
+fn peaking(center: f64, gain_db: f64, bandwidth: f64, sample_rate: f64) -> Biquad {
+    let amplitude = 10f64.powf(gain_db / 40.0);
+    let omega = 2.0 * PI * center / sample_rate;
+    let alpha = omega.sin() * bandwidth / center / 2.0;
+    let a0 = 1.0 + alpha / amplitude;
+    let a1 = -2.0 * omega.cos() / a0;
+    Biquad::new([(1.0 + alpha * amplitude) / a0, a1, (1.0 - alpha * amplitude) / a0], [a1, (1.0 - alpha / amplitude) / a0])
+}
+
+fn check_band(center: f32, bandwidth: f32, sample_rate: f64) -> Result<()> {
+    let nyquist = sample_rate / 2.0;
+    let (low, high) = (f64::from(center) - f64::from(bandwidth) / 2.0, f64::from(center) + f64::from(bandwidth) / 2.0);
+    if !(bandwidth > 0.0 && low > 0.0 && high < nyquist) {
+        return Err(SoundError::InvalidParameter(format!(
+            "The band from {}Hz to {}Hz must be wider than 0 and between 0 and {}Hz", low, high, nyquist)).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32) -> Sound {
+        let samples: Vec<f32> = (0..8000).map(|index| 0.5 * (2.0 * std::f32::consts::PI * frequency * index as f32 / 8000.0).sin()).collect();
+        Sound::from_pcm(&samples, 8000, 1).unwrap()
+    }
+
+    // The RMS level of the second half of a clip, after the filter has settled, in decibels
+    fn level(sound: &Sound) -> f32 {
+        let samples = &sound.pcm().samples[4000..];
+        let power = samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32;
+        10.0 * power.log10()
+    }
+
+    // A second of half-scale white noise
+    fn noise() -> Sound {
+        let samples: Vec<f32> = crate::sound::pcm::noise(8000, 1).iter().map(|sample| 0.5 * sample).collect();
+        Sound::from_pcm(&samples, 8000, 1).unwrap()
+    }
+
+    #[test]
+    fn high_pass() {
+        let low = sine(100.0);
+        let attenuation = level(&low) - level(&low.apply_high_pass(200.0).unwrap());
+        assert!(attenuation >= 6.0, "A 100Hz sine was only lowered by {}dB", attenuation);
+        let high = sine(1000.0);
+        assert!((level(&high) - level(&high.apply_high_pass(200.0).unwrap())).abs() < 0.5);
+        assert!(low.apply_high_pass(0.0).is_err());
+        assert!(low.apply_high_pass(4000.0).is_err());
+    }
+
+    #[test]
+    fn band_pass() {
+        let noise = noise();
+        let filtered = noise.apply_band_pass(1000.0, 400.0).unwrap();
+        // The noise above 2kHz, which is outside of the band
+        let outside = |sound: &Sound| level(&sound.apply_high_pass(2000.0).unwrap());
+        assert!(outside(&noise) - outside(&filtered) > 10.0);
+        assert!(level(&filtered) < level(&noise));
+        assert!(noise.apply_band_pass(100.0, 300.0).is_err());
+        assert!(noise.apply_band_pass(3900.0, 400.0).is_err());
+        assert!(noise.apply_band_pass(1000.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn notch() {
+        let hum = sine(60.0);
+        let attenuation = level(&hum) - level(&hum.apply_notch(60.0, 10.0).unwrap());
+        assert!(attenuation >= 40.0, "A 60Hz sine was only lowered by {}dB", attenuation);
+        let high = sine(1000.0);
+        assert!((level(&high) - level(&high.apply_notch(60.0, 10.0).unwrap())).abs() < 0.5);
+        assert!(hum.apply_notch(60.0, 200.0).is_err());
+    }
+
+    #[test]
+    fn equalizer() {
+        let noise = noise();
+        let flat = noise.apply_equalizer(&[(100.0, 0.0, 50.0), (1000.0, 0.0, 500.0)]).unwrap();
+        for (sample, original) in flat.pcm().samples.iter().zip(noise.pcm().samples.iter()) {
+            assert!((sample - original).abs() < 1e-4);
+        }
+        let tone = sine(1000.0);
+        let boosted = tone.apply_equalizer(&[(1000.0, 6.0, 200.0)]).unwrap();
+        assert!((level(&boosted) - level(&tone) - 6.0).abs() < 0.5);
+        let cut = tone.apply_equalizer(&[(1000.0, -6.0, 200.0), (3000.0, 3.0, 500.0)]).unwrap();
+        assert!((level(&tone) - level(&cut) - 6.0).abs() < 0.5);
+        assert!(tone.apply_equalizer(&[(1000.0, f32::INFINITY, 200.0)]).is_err());
+        assert!(tone.apply_equalizer(&[(1000.0, 0.0, 200.0), (0.0, 0.0, 10.0)]).is_err());
+    }
+}
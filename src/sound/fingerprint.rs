@@ -0,0 +1,55 @@
+use crate::sound::Sound;
+#[cfg(not(target_arch="wasm32"))]
+use sha2::{Digest, Sha256};
+#[cfg(target_arch="wasm32")]
+use stdweb::unstable::TryInto;
+
+impl Sound {
+    /// Get a hash of the contents of the sound file, to tell if two sounds hold the same data
+    ///
+    /// On desktop this is the SHA-256 hash of the file, worked out when the sound is loaded. Sounds
+    /// loaded from identical files have the same fingerprint, wherever they were loaded from.
+    ///
+    /// On the web the file's data isn't available, so the fingerprint is instead a CRC-32 of the
+    /// address the sound was loaded from, in the first four bytes; the rest of the bytes are zero.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.fingerprint
+        }
+        #[cfg(target_arch="wasm32")] {
+            let url: String = js!( return @{&self.sound}.src; ).try_into().unwrap_or_default();
+            let mut fingerprint = [0; 32];
+            fingerprint[..4].copy_from_slice(&crc32(url.as_bytes()).to_be_bytes());
+            fingerprint
+        }
+    }
+}
+
+#[cfg(not(target_arch="wasm32"))]
+pub(super) fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut hash = [0; 32];
+    hash.copy_from_slice(&Sha256::digest(bytes));
+    hash
+}
+
+#[cfg(target_arch="wasm32")]
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_content() {
+        let first = Sound::from_pcm(&[0.0, 0.5, -0.5, 0.25], 44100, 2).unwrap();
+        let second = Sound::from_pcm(&[0.0, 0.5, -0.5, 0.25], 44100, 2).unwrap();
+        let different = Sound::from_pcm(&[0.0, 0.5, -0.5, 0.5], 44100, 2).unwrap();
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_ne!(first.fingerprint(), different.fingerprint());
+        assert_eq!(sha256(b"abc")[..4], [0xba, 0x78, 0x16, 0xbf]);
+    }
+}
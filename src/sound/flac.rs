@@ -0,0 +1,304 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, pcm::f32_to_i16}
+};
+
+// The number of frames in each FLAC frame, except for a shorter last one
+const BLOCK_SIZE: usize = 4096;
+// Samples are stored as 16-bit integers, the precision the decoded samples are kept at
+const BITS_PER_SAMPLE: u32 = 16;
+// The largest Rice parameter that can be written with a 4-bit parameter
+const MAX_RICE_PARAMETER: u32 = 14;
+
+impl Sound {
+    /// Encode the sound clip as a FLAC file, returning the bytes of the file
+    ///
+    /// FLAC is lossless, so decoding the file gives back exactly the samples of this clip, at 16
+    /// bits per sample. The compression level is between 0 and 8, like the reference encoder:
+    /// higher levels try more ways of predicting the samples, which makes the file smaller but
+    /// takes longer. The volume is not applied. Fails if the clip has more than 8 channels,
+    /// which FLAC can't store.
+    ///
+    /// Only available on desktop, with the `flac` feature.
+    pub fn encode_to_flac(&self, compression: u8) -> Result<Vec<u8>> {
+        if compression > 8 {
+            return Err(SoundError::InvalidParameter(format!("The compression level must be between 0 and 8, not {}", compression)).into());
+        }
+        let pcm = self.pcm();
+        if pcm.channels > 8 {
+            return Err(SoundError::InvalidParameter(format!("FLAC can store up to 8 channels, not {}", pcm.channels)).into());
+        }
+        let channels = pcm.channels as usize;
+        let samples: Vec<i32> = pcm.samples.iter().map(|&sample| i32::from(f32_to_i16(sample))).collect();
+        let settings = Settings {
+            max_order: [0, 1, 2, 2, 3, 3, 4, 4, 4][compression as usize],
+            max_partition_order: u32::from(compression) / 2
+        };
+        let mut writer = BitWriter::default();
+        writer.write_bytes(b"fLaC");
+        write_stream_info(&mut writer, pcm.sample_rate, pcm.channels, pcm.frames() as u64);
+        for (number, block) in samples.chunks(BLOCK_SIZE * channels).enumerate() {
+            let start = writer.bytes.len();
+            write_frame_header(&mut writer, number as u64, block.len() / channels, pcm.channels);
+            for channel in 0..channels {
+                let channel: Vec<i32> = block.iter().skip(channel).step_by(channels).cloned().collect();
+                write_subframe(&mut writer, &channel, &settings);
+            }
+            writer.align();
+            let crc = crc16(&writer.bytes[start..]);
+            writer.write(u64::from(crc), 16);
+        }
+        Ok(writer.bytes)
+    }
+}
+
+struct Settings {
+    max_order: usize,
+    max_partition_order: u32
+}
+
+// Writes values most significant bit first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    partial: u8,
+    bits: u32
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        for bit in (0..bits).rev() {
+            self.partial = self.partial << 1 | ((value >> bit) & 1) as u8;
+            self.bits += 1;
+            if self.bits == 8 {
+                self.bytes.push(self.partial);
+                self.partial = 0;
+                self.bits = 0;
+            }
+        }
+    }
+
+    fn write_signed(&mut self, value: i32, bits: u32) {
+        self.write(u64::from(value as u32) & ((1 << bits) - 1), bits);
+    }
+
+    fn write_unary(&mut self, zeros: u32) {
+        for _ in 0..zeros {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write(u64::from(byte), 8);
+        }
+    }
+
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+fn write_stream_info(writer: &mut BitWriter, sample_rate: u32, channels: u16, frames: u64) {
+    // The last metadata block, of type STREAMINFO, which is always 34 bytes long
+    writer.write(1, 1);
+    writer.write(0, 7);
+    writer.write(34, 24);
+    writer.write(BLOCK_SIZE as u64, 16);
+    writer.write(BLOCK_SIZE as u64, 16);
+    // The smallest and largest frame sizes are left unknown
+    writer.write(0, 24);
+    writer.write(0, 24);
+    writer.write(u64::from(sample_rate), 20);
+    writer.write(u64::from(channels) - 1, 3);
+    writer.write(u64::from(BITS_PER_SAMPLE) - 1, 5);
+    writer.write(frames, 36);
+    // An MD5 signature of zero means it wasn't computed
+    writer.write_bytes(&[0; 16]);
+}
+
+fn write_frame_header(writer: &mut BitWriter, number: u64, frames: usize, channels: u16) {
+    let start = writer.bytes.len();
+    writer.write(0b11_1111_1111_1110, 14);
+    // A reserved bit, and a fixed block size
+    writer.write(0, 2);
+    // The block size is written at the end of the header, and the sample rate is in STREAMINFO
+    writer.write(0b0111, 4);
+    writer.write(0b0000, 4);
+    // Each channel is stored on its own, at 16 bits per sample
+    writer.write(u64::from(channels) - 1, 4);
+    writer.write(0b100, 3);
+    writer.write(0, 1);
+    write_utf8(writer, number);
+    writer.write(frames as u64 - 1, 16);
+    let crc = crc8(&writer.bytes[start..]);
+    writer.write(u64::from(crc), 8);
+}
+
+// Write a frame number in the extended UTF-8 coding FLAC uses
+fn write_utf8(writer: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        writer.write(value, 8);
+        return;
+    }
+    // Each continuation byte holds six bits, and takes one bit away from the leading byte
+    let continuation = (1..6).find(|&bytes| value < 1 << (5 * bytes + 6)).unwrap_or(6);
+    writer.write((0xFF00 >> (continuation + 1)) & 0xFF | value >> (6 * continuation), 8);
+    for byte in (0..continuation).rev() {
+        writer.write(0x80 | (value >> (6 * byte)) & 0x3F, 8);
+    }
+}
+
+fn write_subframe(writer: &mut BitWriter, samples: &[i32], settings: &Settings) {
+    if samples.iter().all(|&sample| sample == samples[0]) {
+        write_subframe_header(writer, 0b000000);
+        writer.write_signed(samples[0], BITS_PER_SAMPLE);
+        return;
+    }
+    let verbatim_bits = samples.len() as u64 * u64::from(BITS_PER_SAMPLE);
+    let best = (0..=settings.max_order.min(samples.len() - 1))
+        .map(|order| {
+            let residuals = fixed_residuals(samples, order);
+            let (partition_order, parameters, bits) = best_partitions(&residuals, order, samples.len(), settings.max_partition_order);
+            (order, residuals, partition_order, parameters, bits + order as u64 * u64::from(BITS_PER_SAMPLE))
+        })
+        .min_by_key(|candidate| candidate.4);
+    match best {
+        Some((order, residuals, partition_order, parameters, bits)) if bits < verbatim_bits => {
+            write_subframe_header(writer, 0b001000 | order as u64);
+            for &sample in &samples[..order] {
+                writer.write_signed(sample, BITS_PER_SAMPLE);
+            }
+            // Rice coding with 4-bit parameters
+            writer.write(0b00, 2);
+            writer.write(u64::from(partition_order), 4);
+            let mut residuals = residuals.iter();
+            let partition_length = samples.len() >> partition_order;
+            for (index, &parameter) in parameters.iter().enumerate() {
+                writer.write(u64::from(parameter), 4);
+                let count = if index == 0 { partition_length - order } else { partition_length };
+                for &residual in residuals.by_ref().take(count) {
+                    let value = zigzag(residual);
+                    writer.write_unary(value >> parameter);
+                    writer.write(u64::from(value) & ((1 << parameter) - 1), parameter);
+                }
+            }
+        }
+        _ => {
+            write_subframe_header(writer, 0b000001);
+            for &sample in samples {
+                writer.write_signed(sample, BITS_PER_SAMPLE);
+            }
+        }
+    }
+}
+
+// A padding bit, the type of the subframe, and no wasted bits
+fn write_subframe_header(writer: &mut BitWriter, kind: u64) {
+    writer.write(0, 1);
+    writer.write(kind, 6);
+    writer.write(0, 1);
+}
+
+// The difference between each sample after the warm-up and its prediction from a fixed polynomial
+fn fixed_residuals(samples: &[i32], order: usize) -> Vec<i32> {
+    (order..samples.len())
+        .map(|index| {
+            let s = |back: usize| samples[index - back];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4)
+            }
+        })
+        .collect()
+}
+
+// Choose how to split the residuals into partitions, and the Rice parameter of each partition
+//
+// Returns the partition order, the parameters, and the number of bits the residuals take
+fn best_partitions(residuals: &[i32], order: usize, frames: usize, max_partition_order: u32) -> (u32, Vec<u32>, u64) {
+    (0..=max_partition_order)
+        .filter(|&partition_order| frames.is_multiple_of(1 << partition_order) && frames >> partition_order > order)
+        .map(|partition_order| {
+            let length = frames >> partition_order;
+            let mut start = 0;
+            let mut parameters = Vec::new();
+            let mut bits = 6;
+            for index in 0..1 << partition_order {
+                let count = if index == 0 { length - order } else { length };
+                let (parameter, size) = best_parameter(&residuals[start..start + count]);
+                start += count;
+                parameters.push(parameter);
+                bits += 4 + size;
+            }
+            (partition_order, parameters, bits)
+        })
+        .min_by_key(|candidate| candidate.2)
+        .expect("A single partition is always possible")
+}
+
+// The Rice parameter that codes the residuals in the fewest bits, and that number of bits
+fn best_parameter(residuals: &[i32]) -> (u32, u64) {
+    (0..=MAX_RICE_PARAMETER)
+        .map(|parameter| {
+            let bits = residuals.iter()
+                .map(|&residual| u64::from(zigzag(residual) >> parameter) + 1 + u64::from(parameter))
+                .sum();
+            (parameter, bits)
+        })
+        .min_by_key(|candidate| candidate.1)
+        .expect("There is always a parameter to try")
+}
+
+// Map signed values to unsigned ones, with small magnitudes staying small
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| if crc & 0x80 != 0 { crc << 1 ^ 0x07 } else { crc << 1 })
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ u16::from(byte) << 8, |crc, _| if crc & 0x8000 != 0 { crc << 1 ^ 0x8005 } else { crc << 1 })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::from_bytes;
+
+    #[test]
+    fn round_trip() {
+        let samples: Vec<f32> = (0..20000)
+            .map(|index| 0.5 * (index as f32 * 0.01).sin() * if index % 2 == 0 { 1.0 } else { -0.5 })
+            .collect();
+        let sound = Sound::from_pcm(&samples, 22050, 2).unwrap();
+        for &compression in &[0, 5, 8] {
+            let decoded = from_bytes(sound.encode_to_flac(compression).unwrap()).unwrap();
+            assert_eq!(decoded.pcm(), sound.pcm());
+        }
+        let silence = Sound::from_pcm(&[0.0; 300], 8000, 3).unwrap();
+        assert_eq!(from_bytes(silence.encode_to_flac(2).unwrap()).unwrap().pcm(), silence.pcm());
+        assert!(sound.encode_to_flac(9).is_err());
+    }
+
+    #[test]
+    fn frame_numbers() {
+        let mut writer = BitWriter::default();
+        write_utf8(&mut writer, 0x7F);
+        write_utf8(&mut writer, 0x80);
+        write_utf8(&mut writer, 0x800);
+        assert_eq!(writer.bytes, vec![0x7F, 0xC2, 0x80, 0xE0, 0xA0, 0x80]);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::sound::Sound;
+
+/// An audio file format that sounds can be loaded from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AudioFormat {
+    /// A RIFF WAVE file
+    Wav,
+    /// An OGG container, usually holding Vorbis audio
+    Ogg,
+    /// An MPEG audio file, with or without an ID3 tag
+    Mp3,
+    /// A FLAC file
+    Flac
+}
+
+impl Sound {
+    /// Work out the format of a sound file from the magic bytes at its start
+    ///
+    /// Only the first 12 bytes are inspected, so this doesn't guarantee that the rest of the file
+    /// is valid. Returns None if the bytes don't match any of the known formats.
+    pub fn detect_format(bytes: &[u8]) -> Option<AudioFormat> {
+        detect(bytes)
+    }
+}
+
+pub(super) fn detect(bytes: &[u8]) -> Option<AudioFormat> {
+    let header = &bytes[..bytes.len().min(12)];
+    if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WAVE") {
+        Some(AudioFormat::Wav)
+    } else if header.starts_with(b"OggS") {
+        Some(AudioFormat::Ogg)
+    } else if header.starts_with(b"fLaC") {
+        Some(AudioFormat::Flac)
+    } else if header.starts_with(b"ID3") || is_mpeg_frame(header) {
+        Some(AudioFormat::Mp3)
+    } else {
+        None
+    }
+}
+
+// Check for the sync word and a valid layer at the start of an MPEG audio frame header
+fn is_mpeg_frame(header: &[u8]) -> bool {
+    header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0 && header[1] & 0x06 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_bytes() {
+        assert_eq!(detect(b"RIFF\x24\0\0\0WAVEfmt "), Some(AudioFormat::Wav));
+        assert_eq!(detect(b"RIFF\x24\0\0\0AVI LIST"), None);
+        assert_eq!(detect(b"OggS\0\x02"), Some(AudioFormat::Ogg));
+        assert_eq!(detect(b"fLaC\0\0\0\x22"), Some(AudioFormat::Flac));
+        assert_eq!(detect(b"ID3\x04\0\0"), Some(AudioFormat::Mp3));
+        assert_eq!(detect(&[0xFF, 0xFB, 0x90, 0x64]), Some(AudioFormat::Mp3));
+        assert_eq!(detect(&[0xFF, 0xE0]), None);
+        assert_eq!(detect(b"RIFF"), None);
+        assert_eq!(detect(b""), None);
+    }
+}
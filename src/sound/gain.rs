@@ -0,0 +1,175 @@
+use crate::{
+    Result,
+    sound::{
+        Sound, SoundError,
+        pcm::{PcmCache, amplitude_to_db, db_to_amplitude, encode_wav}
+    }
+};
+use std::sync::Arc;
+
+impl Sound {
+    /// Change the level of the sound clip by `db` decibels, returning the changed clip
+    ///
+    /// Unlike `set_volume`, this changes the samples themselves, so the new level is kept when
+    /// the clip is saved or processed further. Samples that would go past full scale are clipped.
+    /// The gain must be within `[-80, 20]` decibels.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_gain(&self, db: f32) -> Result<Sound> {
+        if !(-80.0..=20.0).contains(&db) {
+            return Err(SoundError::InvalidParameter(format!("The gain must be between -80 and 20 dB, not {}", db)).into());
+        }
+        let gain = db_to_amplitude(db);
+        let samples: Vec<f32> = self.pcm().samples.iter()
+            .map(|sample| (sample * gain).clamp(-1.0, 1.0))
+            .collect();
+        self.with_samples(&samples)
+    }
+
+    /// Scale the samples of the sound clip by `factor`, changing this clip in place
+    ///
+    /// This is like `apply_gain` with a linear factor instead of decibels, but it changes this
+    /// clip instead of creating a new one. Samples that would go past full scale are clipped, and
+    /// the clip is stored as a 16-bit WAV afterwards, so any metadata is lost. Fails unless the
+    /// factor is positive.
+    ///
+    /// The storage is reused when nothing else holds this clip's data. Clones of a sound share its
+    /// data, and so does the `SoundCache` a sound was loaded from, for as long as the cache lives;
+    /// then this clip gets new data, and the clones and the cache keep the old data. Only
+    /// available on desktop.
+    pub fn amplify(&mut self, factor: f32) -> Result<()> {
+        if !(factor > 0.0 && factor.is_finite()) {
+            return Err(SoundError::InvalidParameter(format!("The factor must be positive, not {}", factor)).into());
+        }
+        self.scale_samples(factor)
+    }
+
+    /// Get the largest absolute value of any sample in the sound clip
+    ///
+    /// The volume is not applied, so a clip that reaches full scale has a peak of 1.0. Returns 0
+    /// for a silent or empty clip. Only available on desktop.
+    pub fn peak_amplitude(&self) -> f32 {
+        self.pcm().samples.iter().fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    /// Measure the crest factor of the sound clip: how far its peak is above its RMS level, in dB
+    ///
+    /// This shows how much punch a sound has. Above about 20 decibels the clip is very dynamic,
+    /// while heavily compressed clips are below about 10; a pure sine is at 3 decibels. Returns 0
+    /// for a silent or empty clip. Only available on desktop.
+    pub fn measure_dynamic_range(&self) -> f32 {
+        let samples = &self.pcm().samples;
+        let power = samples.iter().map(|&sample| f64::from(sample) * f64::from(sample)).sum::<f64>() / samples.len() as f64;
+        if samples.is_empty() || power == 0.0 {
+            return 0.0;
+        }
+        amplitude_to_db(self.peak_amplitude()) - 10.0 * power.log10() as f32
+    }
+
+    /// Scale every sound clip in a collection by the same factor, so the loudest peak is at 1.0
+    ///
+    /// Normalizing each clip on its own makes them all equally loud, but this keeps the levels
+    /// of the clips relative to each other, which is useful for sounds from different sources
+    /// that should still be balanced against each other. If every clip is silent, nothing is
+    /// changed.
+    ///
+    /// The clips are changed in place like `amplify`. Only available on desktop.
+    pub fn batch_normalize(sounds: &mut [Sound]) -> Result<()> {
+        let peak = sounds.iter().map(Sound::peak_amplitude).fold(0.0, f32::max);
+        if peak == 0.0 || peak == 1.0 {
+            return Ok(());
+        }
+        for sound in sounds.iter_mut() {
+            sound.scale_samples(1.0 / peak)?;
+        }
+        Ok(())
+    }
+
+    // Store the samples scaled by the factor, reusing the storage if no clones share it
+    fn scale_samples(&mut self, factor: f32) -> Result<()> {
+        let pcm = self.pcm();
+        let samples: Vec<f32> = pcm.samples.iter().map(|sample| (sample * factor).clamp(-1.0, 1.0)).collect();
+        let bytes = encode_wav(&samples, pcm.sample_rate, pcm.channels)?;
+        #[cfg(feature = "fingerprint")] {
+            self.fingerprint = crate::sound::fingerprint::sha256(&bytes);
+        }
+        match Arc::get_mut(&mut self.val) {
+            Some(val) => *val = bytes,
+            None => self.val = Arc::new(bytes)
+        }
+        self.decoded = PcmCache::default();
+        self.metadata = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::SoundCache;
+    use futures::Future;
+    use std::fs;
+
+    #[test]
+    fn gain() {
+        let sound = Sound::from_pcm(&[0.5, -0.5, 0.25, 0.0], 100, 2).unwrap();
+        let quieter = sound.apply_gain(-6.0206).unwrap();
+        for (sample, expected) in quieter.pcm().samples.iter().zip(&[0.25, -0.25, 0.125, 0.0]) {
+            assert!((sample - expected).abs() < 1e-3);
+        }
+        let clipped = sound.apply_gain(12.0).unwrap();
+        assert!(clipped.pcm().samples[0] > 0.99);
+        assert!(clipped.pcm().samples[1] < -0.99);
+        assert!(sound.apply_gain(-81.0).is_err());
+        assert!(sound.apply_gain(20.5).is_err());
+    }
+
+    #[test]
+    fn amplify() {
+        let mut sound = Sound::from_pcm(&[0.5, -0.25, 0.75, 0.0], 100, 2).unwrap();
+        sound.amplify(2.0).unwrap();
+        for (sample, expected) in sound.pcm().samples.iter().zip(&[1.0, -0.5, 1.0, 0.0]) {
+            assert!((sample - expected).abs() < 1e-3);
+        }
+        assert_eq!(sound.pcm().channels, 2);
+        assert!(sound.amplify(0.0).is_err());
+        let clone = sound.clone();
+        sound.amplify(0.5).unwrap();
+        assert!((sound.peak_amplitude() - 0.5).abs() < 1e-3);
+        assert!((clone.peak_amplitude() - 1.0).abs() < 1e-3);
+        // A cache always holds the data of the sounds loaded from it
+        let path = std::env::temp_dir().join(format!("quicksilver-amplified-{}.wav", std::process::id()));
+        fs::write(&path, encode_wav(&[0.25; 4], 100, 1).unwrap()).unwrap();
+        let cache = SoundCache::new();
+        let mut cached = Sound::load_with_cache(&path, &cache).wait().unwrap();
+        fs::remove_file(&path).unwrap();
+        cached.amplify(2.0).unwrap();
+        assert!((cached.peak_amplitude() - 0.5).abs() < 1e-3);
+        assert!((Sound::load_with_cache(&path, &cache).wait().unwrap().peak_amplitude() - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn batch_normalize() {
+        let loud = Sound::from_pcm(&[0.5, -0.25], 100, 1).unwrap();
+        let quiet = Sound::from_pcm(&[0.125, 0.0], 100, 2).unwrap();
+        let shared = quiet.clone();
+        let mut sounds = vec![loud, quiet];
+        Sound::batch_normalize(&mut sounds).unwrap();
+        assert!((sounds[0].peak_amplitude() - 1.0).abs() < 1e-3);
+        assert!((sounds[1].peak_amplitude() - 0.25).abs() < 1e-3);
+        assert!((shared.peak_amplitude() - 0.125).abs() < 1e-3);
+        let mut silent = vec![Sound::from_pcm(&[0.0; 4], 100, 1).unwrap()];
+        Sound::batch_normalize(&mut silent).unwrap();
+        assert_eq!(silent[0].peak_amplitude(), 0.0);
+    }
+
+    #[test]
+    fn dynamic_range() {
+        let samples: Vec<f32> = (0..8000).map(|index| 0.5 * (2.0 * std::f32::consts::PI * 200.0 * index as f32 / 8000.0).sin()).collect();
+        let sine = Sound::from_pcm(&samples, 8000, 1).unwrap();
+        assert!((sine.measure_dynamic_range() - 3.01).abs() < 0.05, "A sine has a crest factor of {}dB", sine.measure_dynamic_range());
+        let square = Sound::from_pcm(&[0.5, -0.5, 0.5, -0.5], 100, 2).unwrap();
+        assert!(square.measure_dynamic_range().abs() < 1e-3);
+        assert_eq!(Sound::from_pcm(&[0.0; 4], 100, 1).unwrap().measure_dynamic_range(), 0.0);
+    }
+}
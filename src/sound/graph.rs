@@ -0,0 +1,174 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, StopHandle, play_source}
+};
+#[cfg(feature = "dsp")]
+use crate::sound::effects::{AudioEffect, EffectChain};
+use rodio::{
+    dynamic_mixer,
+    source::Source
+};
+
+// Every node is mixed at this format, regardless of the format of the sounds within it
+const MIX_CHANNELS: u16 = 2;
+const MIX_SAMPLE_RATE: u32 = 44100;
+
+/// A handle to a node within an `AudioGraph`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug)]
+enum NodeKind {
+    Sound(Sound),
+    Gain(f32)
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    kind: NodeKind,
+    inputs: Vec<NodeId>,
+    has_output: bool
+}
+
+/// A graph that routes sounds through effect nodes into a single mixed output
+///
+/// Each node either plays a sound or applies an effect to everything connected into it. A node
+/// mixes its own output with the output of all the nodes connected into it, and any node that is
+/// not connected to another node is mixed into the master output.
+///
+/// The graph is only available on desktop.
+#[derive(Clone, Debug)]
+pub struct AudioGraph {
+    nodes: Vec<Node>,
+    master_gain: f32,
+    #[cfg(feature = "dsp")]
+    effects: EffectChain
+}
+
+impl AudioGraph {
+    /// Create an empty graph with a master gain of 1
+    pub fn new() -> AudioGraph {
+        AudioGraph {
+            nodes: Vec::new(),
+            master_gain: 1f32,
+            #[cfg(feature = "dsp")]
+            effects: EffectChain::default()
+        }
+    }
+
+    /// Add a node that plays the given sound
+    ///
+    /// The volume and looping settings of the sound are used when the graph is played.
+    pub fn add_node(&mut self, sound: Sound) -> NodeId {
+        self.push(NodeKind::Sound(sound))
+    }
+
+    /// Add an effect node that multiplies the volume of everything connected into it
+    pub fn add_gain(&mut self, gain: f32) -> NodeId {
+        self.push(NodeKind::Gain(gain))
+    }
+
+    fn push(&mut self, kind: NodeKind) -> NodeId {
+        self.nodes.push(Node {
+            kind,
+            inputs: Vec::new(),
+            has_output: false
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Route the output of one node into another
+    ///
+    /// Fails if either node is not part of this graph, or if the connection would create a cycle.
+    pub fn connect(&mut self, from: NodeId, to: NodeId) -> Result<()> {
+        if from.0 >= self.nodes.len() || to.0 >= self.nodes.len() {
+            return Err(SoundError::InvalidParameter("The node is not part of this graph".to_owned()).into());
+        }
+        if self.feeds_into(to, from) {
+            return Err(SoundError::InvalidParameter("Connecting the nodes would create a cycle".to_owned()).into());
+        }
+        self.nodes[to.0].inputs.push(from);
+        self.nodes[from.0].has_output = true;
+        Ok(())
+    }
+
+    // Check if the output of a node ends up (directly or not) in the target node
+    fn feeds_into(&self, node: NodeId, target: NodeId) -> bool {
+        node == target || self.nodes[target.0].inputs.iter().any(|&input| self.feeds_into(node, input))
+    }
+
+    /// Get the gain applied to the mixed output of the graph
+    pub fn master_gain(&self) -> f32 {
+        self.master_gain
+    }
+
+    /// Set the gain applied to the mixed output of the graph
+    pub fn set_master_gain(&mut self, gain: f32) {
+        self.master_gain = gain;
+    }
+
+    /// Set the effects that process the mixed output, in the order they are applied
+    ///
+    /// The effects run after the master gain, and replace any effects set before. Clones of the
+    /// graph share its effects, and so do all of its playbacks, including ones already playing.
+    /// Only available with the `dsp` feature.
+    #[cfg(feature = "dsp")]
+    pub fn set_effect_chain(&mut self, effects: Vec<Box<dyn AudioEffect>>) {
+        self.effects.set(effects);
+    }
+
+    fn node_source(&self, id: NodeId) -> Result<Box<dyn Source<Item = f32> + Send>> {
+        let node = &self.nodes[id.0];
+        let (controller, mixer) = dynamic_mixer::mixer(MIX_CHANNELS, MIX_SAMPLE_RATE);
+        if let NodeKind::Sound(sound) = &node.kind {
+            controller.add(sound.playback_source()?.0);
+        }
+        for &input in node.inputs.iter() {
+            controller.add(self.node_source(input)?);
+        }
+        Ok(match node.kind {
+            NodeKind::Sound(_) => Box::new(mixer),
+            NodeKind::Gain(gain) => Box::new(mixer.amplify(gain))
+        })
+    }
+
+    /// Play every sound in the graph through its effects
+    ///
+    /// Changes to the graph after this is called do not affect the playing output.
+    pub fn play(&self) -> Result<StopHandle> {
+        let (controller, mixer) = dynamic_mixer::mixer(MIX_CHANNELS, MIX_SAMPLE_RATE);
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !node.has_output {
+                controller.add(self.node_source(NodeId(index))?);
+            }
+        }
+        let output = mixer.amplify(self.master_gain);
+        #[cfg(feature = "dsp")]
+        let output = self.effects.apply(output);
+        play_source(output)
+    }
+}
+
+impl Default for AudioGraph {
+    fn default() -> AudioGraph {
+        AudioGraph::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_cycles() {
+        let mut graph = AudioGraph::new();
+        let sound = graph.add_node(Sound::from_pcm(&[0.0; 4], 44100, 1).unwrap());
+        let first = graph.add_gain(0.5);
+        let second = graph.add_gain(0.5);
+        assert!(graph.connect(sound, first).is_ok());
+        assert!(graph.connect(first, second).is_ok());
+        assert!(graph.connect(second, sound).is_err());
+        assert!(graph.connect(second, second).is_err());
+        assert!(graph.connect(second, NodeId(10)).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::sound::{Sound, spectrum::Stft};
+
+// The analysis frames are this many samples long, and start this many samples apart
+const FRAME_SIZE: usize = 4096;
+const HOP_SIZE: usize = 2048;
+// Only frequencies in the five octaves from C2 to C7 count towards the pitch classes
+const LOWEST_NOTE: f32 = 65.406;
+const OCTAVES: f32 = 5.0;
+// A sound whose loudest and quietest pitch classes are closer than this is too flat to have a key
+const MIN_CONTRAST: f32 = 0.25;
+// The key must correlate at least this well with the chroma
+const MIN_CORRELATION: f32 = 0.5;
+
+// The Krumhansl-Schmuckler key profiles, starting from the tonic
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// One of the twelve pitch classes of Western music
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pitch {
+    /// C
+    C,
+    /// C sharp, or D flat
+    CSharp,
+    /// D
+    D,
+    /// D sharp, or E flat
+    DSharp,
+    /// E
+    E,
+    /// F
+    F,
+    /// F sharp, or G flat
+    FSharp,
+    /// G
+    G,
+    /// G sharp, or A flat
+    GSharp,
+    /// A
+    A,
+    /// A sharp, or B flat
+    ASharp,
+    /// B
+    B
+}
+
+impl Pitch {
+    const ALL: [Pitch; 12] = [
+        Pitch::C, Pitch::CSharp, Pitch::D, Pitch::DSharp, Pitch::E, Pitch::F,
+        Pitch::FSharp, Pitch::G, Pitch::GSharp, Pitch::A, Pitch::ASharp, Pitch::B
+    ];
+}
+
+/// Whether a key is major or minor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// A major key, which usually sounds bright
+    Major,
+    /// A natural minor key, which usually sounds darker
+    Minor
+}
+
+/// The key of a piece of music, like C major or A minor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MusicKey {
+    /// The tonic of the key, which the music tends to return to
+    pub root: Pitch,
+    /// Whether the key is major or minor
+    pub mode: Mode
+}
+
+impl Sound {
+    /// Estimate the musical key of the sound clip
+    ///
+    /// The energy of the clip is gathered into the twelve pitch classes, and compared with the
+    /// Krumhansl-Schmuckler profiles of how often each note is heard in every major and minor
+    /// key. This is a heuristic: closely related keys, like C major and A minor, are easily
+    /// confused, and short clips or single notes may not have enough notes to tell them apart.
+    ///
+    /// Returns None for silence and for sounds without a clear pitch, like noise or speech. Only
+    /// available on desktop with the `dsp` feature.
+    pub fn detect_key(&self) -> Option<MusicKey> {
+        let pcm = self.pcm();
+        let chroma = chroma(&pcm.to_mono(), pcm.sample_rate);
+        let loudest = chroma.iter().cloned().fold(0.0, f32::max);
+        let quietest = chroma.iter().cloned().fold(loudest, f32::min);
+        if loudest <= 0.0 || (loudest - quietest) / loudest < MIN_CONTRAST {
+            return None;
+        }
+        let mut best = (MIN_CORRELATION, None);
+        for (root, &pitch) in Pitch::ALL.iter().enumerate() {
+            for &(mode, profile) in &[(Mode::Major, &MAJOR_PROFILE), (Mode::Minor, &MINOR_PROFILE)] {
+                // The profile starts from the tonic, and the chroma starts from C
+                let rotated: Vec<f32> = (0..12).map(|class| profile[(class + 12 - root) % 12]).collect();
+                let correlation = correlation(&chroma, &rotated);
+                if correlation >= best.0 {
+                    best = (correlation, Some(MusicKey { root: pitch, mode }));
+                }
+            }
+        }
+        best.1
+    }
+}
+
+// The total magnitude of each pitch class, starting from C, over all frames of mono samples
+fn chroma(samples: &[f32], sample_rate: u32) -> [f32; 12] {
+    let mut stft = Stft::new(FRAME_SIZE);
+    let bin_width = sample_rate as f32 / FRAME_SIZE as f32;
+    let highest_note = LOWEST_NOTE * 2f32.powf(OCTAVES);
+    let mut chroma = [0.0; 12];
+    for frame in 0..samples.len().div_ceil(HOP_SIZE) {
+        let magnitudes = stft.magnitudes(samples, frame * HOP_SIZE);
+        for (bin, magnitude) in magnitudes.iter().enumerate() {
+            let frequency = bin as f32 * bin_width;
+            if frequency < LOWEST_NOTE || frequency >= highest_note {
+                continue;
+            }
+            // The MIDI note number, where 60 is middle C
+            let note = (69.0 + 12.0 * (frequency / 440.0).log2()).round() as usize;
+            chroma[note % 12] += magnitude;
+        }
+    }
+    chroma
+}
+
+// The Pearson correlation of two series of values
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let covariance: f32 = a.iter().zip(b).map(|(a, b)| (a - mean_a) * (b - mean_b)).sum();
+    let spread = |values: &[f32], mean: f32| values.iter().map(|value| (value - mean) * (value - mean)).sum::<f32>().sqrt();
+    let spreads = spread(a, mean_a) * spread(b, mean_b);
+    if spreads > 0.0 { covariance / spreads } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    // A chord of sine waves, given as note frequencies and their volumes
+    fn chord(notes: &[(f32, f32)]) -> Sound {
+        let samples: Vec<f32> = (0..16000)
+            .map(|index| {
+                let time = index as f32 / 8000.0;
+                notes.iter().map(|&(frequency, volume)| volume * (2.0 * PI * frequency * time).sin()).sum()
+            })
+            .collect();
+        Sound::from_pcm(&samples, 8000, 1).unwrap()
+    }
+
+    #[test]
+    fn triads() {
+        let c_major = chord(&[(261.63, 0.3), (329.63, 0.3), (392.0, 0.3)]);
+        assert_eq!(c_major.detect_key(), Some(MusicKey { root: Pitch::C, mode: Mode::Major }));
+        let a_minor = chord(&[(220.0, 0.4), (261.63, 0.2), (329.63, 0.3)]);
+        assert_eq!(a_minor.detect_key(), Some(MusicKey { root: Pitch::A, mode: Mode::Minor }));
+        let g_major = chord(&[(196.0, 0.4), (246.94, 0.2), (293.66, 0.3)]);
+        assert_eq!(g_major.detect_key(), Some(MusicKey { root: Pitch::G, mode: Mode::Major }));
+    }
+
+    #[test]
+    fn atonal() {
+        assert_eq!(Sound::from_pcm(&[0.0; 8000], 8000, 1).unwrap().detect_key(), None);
+        let noise = crate::sound::pcm::noise(32000, 1);
+        assert_eq!(Sound::from_pcm(&noise, 8000, 1).unwrap().detect_key(), None);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    error::QuicksilverError,
+    sound::Sound
+};
+use futures::{Async, Future, Poll};
+use std::{path::Path, time::Duration};
+#[cfg(not(target_arch="wasm32"))]
+use {
+    crate::sound::{SoundError, load},
+    futures::task,
+    std::{
+        path::PathBuf,
+        thread,
+        time::Instant
+    }
+};
+#[cfg(target_arch="wasm32")]
+use {
+    crate::sound::wasm_sound_error,
+    stdweb::{Value, unstable::TryInto}
+};
+
+/// A sound that is being loaded, created by `Sound::load`
+///
+/// The loader is a future that resolves to the sound once it has loaded. While it loads, its
+/// progress can be checked without waiting for it, like when drawing a loading screen.
+#[derive(Debug)]
+pub struct SoundLoader {
+    #[cfg(not(target_arch="wasm32"))]
+    path: PathBuf,
+    // The result of the latest attempt, until it is returned or retried
+    #[cfg(not(target_arch="wasm32"))]
+    result: Option<Result<Sound, SoundError>>,
+    #[cfg(target_arch="wasm32")]
+    path: String,
+    #[cfg(target_arch="wasm32")]
+    sound: Value,
+    retries: u32,
+    max_retries: u32,
+    backoff: Duration,
+    // When the next attempt is due
+    #[cfg(not(target_arch="wasm32"))]
+    retry_at: Option<Instant>,
+    // When the next attempt is due, in milliseconds on the page's clock
+    #[cfg(target_arch="wasm32")]
+    retry_at: Option<f64>,
+    // The ready state the audio element has to reach to count as loaded
+    #[cfg(target_arch="wasm32")]
+    ready_state: i32
+}
+
+impl SoundLoader {
+    #[cfg(not(target_arch="wasm32"))]
+    pub(super) fn new(path: &Path, max_retries: u32, backoff: Duration) -> SoundLoader {
+        SoundLoader {
+            path: path.to_owned(),
+            result: Some(load(path)),
+            retries: 0,
+            max_retries,
+            backoff,
+            retry_at: None
+        }
+    }
+
+    #[cfg(target_arch="wasm32")]
+    pub(super) fn new(path: &Path, max_retries: u32, backoff: Duration) -> SoundLoader {
+        let path = path.to_str().expect("Path must be stringifiable").to_owned();
+        SoundLoader {
+            sound: start_loading(&path),
+            path,
+            retries: 0,
+            max_retries,
+            backoff,
+            retry_at: None,
+            ready_state: HAVE_ENOUGH_DATA
+        }
+    }
+
+    // Finish loading as soon as playback can start, instead of once the whole file is buffered
+    pub(super) fn streaming(self) -> SoundLoader {
+        #[cfg(target_arch="wasm32")] {
+            SoundLoader {
+                ready_state: HAVE_CURRENT_DATA,
+                ..self
+            }
+        }
+        #[cfg(not(target_arch="wasm32"))] {
+            self
+        }
+    }
+
+    /// Check how much of the sound has loaded, from 0 to 1
+    ///
+    /// On desktop sounds are loaded all at once, so this is 1, or 0 while the loader waits to try
+    /// a failed load again. On the web it is the
+    /// portion of the file the browser has buffered so far, which is 0 until the browser knows
+    /// how long the sound is.
+    pub fn progress(&self) -> f32 {
+        #[cfg(not(target_arch="wasm32"))] {
+            if self.retry_at.is_some() { 0.0 } else { 1.0 }
+        }
+        #[cfg(target_arch="wasm32")] {
+            let progress: f64 = js! {
+                const audio = @{&self.sound};
+                if (audio.readyState === 4) { return 1; }
+                if (!(audio.duration > 0) || !isFinite(audio.duration) || audio.buffered.length === 0) { return 0; }
+                return audio.buffered.end(audio.buffered.length - 1) / audio.duration;
+            }.try_into().unwrap_or(0.0);
+            progress.max(0.0).min(1.0) as f32
+        }
+    }
+}
+
+impl Future for SoundLoader {
+    type Item = Sound;
+    type Error = QuicksilverError;
+
+    #[cfg(not(target_arch="wasm32"))]
+    fn poll(&mut self) -> Poll<Sound, QuicksilverError> {
+        if let Some(time) = self.retry_at {
+            if Instant::now() < time {
+                return Ok(Async::NotReady);
+            }
+            self.result = Some(load(&self.path));
+            self.retry_at = None;
+        }
+        match self.result.take().expect("A SoundLoader was polled after it completed") {
+            Ok(sound) => Ok(Async::Ready(sound)),
+            Err(SoundError::IOError(_)) if self.retries < self.max_retries => {
+                let backoff = self.backoff.saturating_mul(2u32.saturating_pow(self.retries));
+                self.retry_at = Some(Instant::now() + backoff);
+                self.retries += 1;
+                // The task is woken once the wait is over, so nothing has to block in the meantime
+                let task = task::current();
+                thread::spawn(move || {
+                    thread::sleep(backoff);
+                    task.notify();
+                });
+                Ok(Async::NotReady)
+            }
+            Err(err) => Err(err.into())
+        }
+    }
+
+    #[cfg(target_arch="wasm32")]
+    fn poll(&mut self) -> Poll<Sound, QuicksilverError> {
+        if let Some(time) = self.retry_at {
+            if now() < time {
+                return Ok(Async::NotReady);
+            }
+            self.sound = start_loading(&self.path);
+            self.retry_at = None;
+        }
+        let error = js! ( return @{&self.sound}.hasError ).try_into();
+        let ready: std::result::Result<i32, _> = js! ( return @{&self.sound}.readyState ).try_into();
+        match (error, ready) {
+            (Ok(false), Ok(ready)) if ready >= self.ready_state => Ok(Async::Ready(Sound::from_element(self.sound.clone()))),
+            (Ok(true), _) if self.retries < self.max_retries => {
+                let backoff = self.backoff.as_secs_f64() * 1000.0 * 2f64.powi(self.retries as i32);
+                self.retry_at = Some(now() + backoff);
+                self.retries += 1;
+                Ok(Async::NotReady)
+            }
+            (Ok(true), _) => Err(wasm_sound_error("Sound file not found or could not load")),
+            (Ok(false), Ok(_)) => Ok(Async::NotReady),
+            (Err(_), _) => Err(wasm_sound_error("Checking sound network state failed")),
+            (_, Err(_)) => Err(wasm_sound_error("Checking sound ready state failed")),
+        }
+    }
+}
+
+// The ready states of an audio element, for when it's enough to play now or all the way through
+#[cfg(target_arch="wasm32")]
+const HAVE_CURRENT_DATA: i32 = 2;
+#[cfg(target_arch="wasm32")]
+const HAVE_ENOUGH_DATA: i32 = 4;
+
+#[cfg(target_arch="wasm32")]
+fn start_loading(path: &str) -> Value {
+    js! {
+        const audio = new Audio(@{path});
+        audio.hasError = false;
+        audio.onerror = (error) => audio.hasError = true;
+        return audio;
+    }
+}
+
+#[cfg(target_arch="wasm32")]
+fn now() -> f64 {
+    js! ( return performance.now(); ).try_into().unwrap_or(0.0)
+}
@@ -0,0 +1,209 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+#[cfg(not(target_arch="wasm32"))]
+use crate::sound::metadata::Reader;
+use std::time::Duration;
+
+// Loops shorter than this buzz rather than repeat
+const MIN_LOOP_MS: u64 = 100;
+
+/// A part of a sound clip that repeats when the sound loops
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LoopRegion {
+    /// Where the repeated part starts, from the start of the clip
+    pub start: Duration,
+    /// Where the repeated part ends, from the start of the clip
+    pub end: Duration
+}
+
+impl LoopRegion {
+    /// Create a loop region between two points in a clip
+    pub fn new(start: Duration, end: Duration) -> LoopRegion {
+        LoopRegion { start, end }
+    }
+}
+
+impl Sound {
+    /// Check if a pair of loop points can be used to loop part of the sound clip
+    ///
+    /// The start must come before the end, the end must lie within the sound, and the loop must be
+    /// at least 100 milliseconds long; shorter loops are heard as a buzz rather than a repetition.
+    /// Returns a `SoundError::InvalidParameter` explaining the first problem that was found.
+    ///
+    /// Only available on desktop.
+    #[cfg(not(target_arch="wasm32"))]
+    pub fn validate_loop_points(&self, start: Duration, end: Duration) -> Result<()> {
+        let pcm = self.pcm();
+        check_loop_points(start, end, Some(pcm.duration_of(pcm.frames())))
+            .map_err(|message| SoundError::InvalidParameter(message).into())
+    }
+
+    /// Set the part of the sound clip that repeats when it loops
+    ///
+    /// With `LoopMode::Repeat`, the sound plays from its start to the end of the region, and then
+    /// repeats the region over and over; the rest of the clip is never played. Other loop modes
+    /// ignore the region. The region must pass `validate_loop_points`, except that on the web the
+    /// end is only checked against the length of the sound if the length is already known.
+    ///
+    /// On the web, the jump back to the start of the region is only made a few times a second, so
+    /// the loop is not seamless there.
+    pub fn set_loop_region(&mut self, region: LoopRegion) -> Result<()> {
+        let duration = Some(self.duration_or_zero()).filter(|duration| *duration > Duration::ZERO);
+        check_loop_points(region.start, region.end, duration)
+            .map_err(SoundError::InvalidParameter)?;
+        self.loop_region = Some(region);
+        Ok(())
+    }
+
+    /// Remove the loop region, so the whole sound clip repeats when it loops
+    pub fn clear_loop_region(&mut self) {
+        self.loop_region = None;
+    }
+
+    /// Check if part of the sound clip has been set to repeat when it loops
+    pub fn has_loop_region(&self) -> bool {
+        self.loop_region.is_some()
+    }
+
+    /// Get the part of the sound clip that repeats when it loops, if one was set
+    pub fn loop_region(&self) -> Option<LoopRegion> {
+        self.loop_region
+    }
+
+    /// Set the loop region from the loop points stored in a WAV file, returning the region
+    ///
+    /// WAV files from audio tools often mark a loop in a `smpl` chunk. If the file has one, the
+    /// first loop becomes the loop region, as long as it is a valid region that ends within the clip.
+    /// This is done automatically when a sound is loaded, so it only needs to be called again
+    /// after the region was changed. Returns None and leaves the region alone for other files.
+    ///
+    /// Only available on desktop.
+    #[cfg(not(target_arch="wasm32"))]
+    pub fn extract_loop_metadata_from_smpl_chunk(&mut self) -> Option<LoopRegion> {
+        let region = smpl_loop_region(&self.val)?;
+        self.loop_region = Some(region);
+        Some(region)
+    }
+}
+
+// Read the first loop from the smpl chunk of WAV data, converted from sample frames to times
+#[cfg(not(target_arch="wasm32"))]
+pub(super) fn smpl_loop_region(data: &[u8]) -> Option<LoopRegion> {
+    let mut reader = Reader::new(data);
+    if reader.take(4)? != b"RIFF" || reader.take(8)?.get(4..) != Some(b"WAVE") {
+        return None;
+    }
+    let mut sample_rate = None;
+    let mut block_align = None;
+    let mut data_length = None;
+    let mut region = None;
+    while let (Some(id), Some(length)) = (reader.take(4), reader.u32_le()) {
+        // Chunks are padded to an even length
+        let mut chunk = Reader::new(reader.take(length as usize + (length as usize & 1)).or_else(|| reader.take(length as usize))?);
+        match id {
+            b"fmt " => {
+                chunk.take(4)?;
+                sample_rate = Some(chunk.u32_le()?).filter(|&rate| rate > 0);
+                chunk.take(4)?;
+                block_align = Some(chunk.u16_le()?).filter(|&align| align > 0);
+            }
+            b"data" => data_length = Some(u64::from(length)),
+            b"smpl" => {
+                chunk.take(28)?;
+                if chunk.u32_le()? > 0 {
+                    chunk.take(12)?;
+                    // The end is the last frame that is played before jumping back
+                    region = Some((chunk.u32_le()?, chunk.u32_le()?.checked_add(1)?));
+                }
+            }
+            _ => ()
+        }
+    }
+    let sample_rate = u64::from(sample_rate?);
+    let (start, end) = region?;
+    let to_duration = |frame: u32| Duration::from_nanos(u64::from(frame) * 1_000_000_000 / sample_rate);
+    let region = LoopRegion::new(to_duration(start), to_duration(end));
+    // Loops that run past the end of the audio are dropped rather than trusted
+    let frames = data_length? / u64::from(block_align?);
+    let duration = Duration::from_nanos(frames * 1_000_000_000 / sample_rate);
+    check_loop_points(region.start, region.end, Some(duration)).ok()?;
+    Some(region)
+}
+
+fn check_loop_points(start: Duration, end: Duration, duration: Option<Duration>) -> std::result::Result<(), String> {
+    if start >= end {
+        Err(format!("The loop start ({:?}) must come before the loop end ({:?})", start, end))
+    } else if let Some(duration) = duration.filter(|&duration| end > duration) {
+        Err(format!("The loop end ({:?}) is past the end of the sound ({:?})", end, duration))
+    } else if end - start < Duration::from_millis(MIN_LOOP_MS) {
+        Err(format!("The loop is {:?} long, but it must be at least {}ms", end - start, MIN_LOOP_MS))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_points() {
+        // One second long
+        let sound = Sound::from_pcm(&[0.0; 100], 100, 1).unwrap();
+        let ms = Duration::from_millis;
+        assert!(sound.validate_loop_points(ms(0), ms(1000)).is_ok());
+        assert!(sound.validate_loop_points(ms(200), ms(300)).is_ok());
+        assert!(sound.validate_loop_points(ms(500), ms(500)).is_err());
+        assert!(sound.validate_loop_points(ms(600), ms(500)).is_err());
+        assert!(sound.validate_loop_points(ms(500), ms(1010)).is_err());
+        assert!(sound.validate_loop_points(ms(500), ms(550)).is_err());
+    }
+
+    #[test]
+    fn loop_region() {
+        let mut sound = Sound::from_pcm(&[0.0; 100], 100, 1).unwrap();
+        let ms = Duration::from_millis;
+        assert!(!sound.has_loop_region());
+        assert!(sound.set_loop_region(LoopRegion::new(ms(500), ms(1010))).is_err());
+        let region = LoopRegion::new(ms(200), ms(800));
+        sound.set_loop_region(region).unwrap();
+        assert!(sound.has_loop_region());
+        assert_eq!(sound.loop_region(), Some(region));
+        sound.clear_loop_region();
+        assert_eq!(sound.loop_region(), None);
+    }
+
+    // Add a smpl chunk with one loop between two frames to WAV data
+    fn with_smpl_loop(mut wav: Vec<u8>, start: u32, end: u32) -> Vec<u8> {
+        let mut chunk = b"smpl".to_vec();
+        chunk.extend_from_slice(&60u32.to_le_bytes());
+        chunk.extend_from_slice(&[0; 28]);
+        chunk.extend_from_slice(&1u32.to_le_bytes());
+        chunk.extend_from_slice(&[0; 12]);
+        chunk.extend_from_slice(&start.to_le_bytes());
+        chunk.extend_from_slice(&end.to_le_bytes());
+        chunk.extend_from_slice(&[0; 8]);
+        wav.extend_from_slice(&chunk);
+        let riff_length = wav.len() as u32 - 8;
+        wav[4..8].copy_from_slice(&riff_length.to_le_bytes());
+        wav
+    }
+
+    #[test]
+    fn smpl_chunk() {
+        let wav = crate::sound::pcm::encode_wav(&[0.0; 100], 100, 1).unwrap();
+        let ms = Duration::from_millis;
+        let mut sound = crate::sound::from_bytes(with_smpl_loop(wav.clone(), 20, 79)).unwrap();
+        assert_eq!(sound.loop_region(), Some(LoopRegion::new(ms(200), ms(800))));
+        sound.clear_loop_region();
+        assert_eq!(sound.extract_loop_metadata_from_smpl_chunk(), Some(LoopRegion::new(ms(200), ms(800))));
+        assert_eq!(smpl_loop_region(&with_smpl_loop(wav.clone(), 20, 21)), None);
+        assert_eq!(smpl_loop_region(&wav), None);
+        // The clip is 100 frames long, so a loop ending after frame 99 doesn't fit
+        assert_eq!(smpl_loop_region(&with_smpl_loop(wav.clone(), 20, 99)), Some(LoopRegion::new(ms(200), ms(1000))));
+        assert_eq!(smpl_loop_region(&with_smpl_loop(wav.clone(), 20, 100)), None);
+        assert_eq!(crate::sound::from_bytes(with_smpl_loop(wav, 20, 5000)).unwrap().loop_region(), None);
+    }
+}
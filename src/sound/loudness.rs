@@ -0,0 +1,170 @@
+use crate::sound::{Sound, biquad::Biquad, pcm::amplitude_to_db};
+use std::{
+    f64::consts::PI,
+    time::Duration
+};
+
+// Loudness is measured over blocks of 400ms that start every 100ms
+const BLOCK_MS: u32 = 400;
+const STEP_MS: u32 = 100;
+// Blocks quieter than this are ignored entirely
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+// Blocks this much quieter than the ungated average are ignored
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+impl Sound {
+    /// Measure the integrated loudness of the sound clip, in LUFS, as specified by EBU R128
+    ///
+    /// The samples are K-weighted to approximate how loud different frequencies sound, and quiet
+    /// passages are gated out of the average, following ITU-R BS.1770-4. Six-channel clips are
+    /// treated as 5.1 surround, with the LFE channel ignored and the surround channels weighted
+    /// more heavily; every channel of any other layout is weighted equally. Returns negative
+    /// infinity for clips that are silent or shorter than 400 milliseconds.
+    ///
+    /// Only available on desktop with the `dsp` feature.
+    pub fn loudness_k_weighted(&self) -> f32 {
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let mut samples: Vec<f64> = pcm.samples.iter().map(|&sample| f64::from(sample)).collect();
+        let (shelf, high_pass) = k_weighting(f64::from(pcm.sample_rate));
+        shelf.filter_interleaved(&mut samples, channels);
+        high_pass.filter_interleaved(&mut samples, channels);
+        let weights: Vec<f64> = (0..channels).map(|channel| channel_weight(channel, channels)).collect();
+        // Very low sample rates still need blocks at least a frame long, or they'd never advance
+        let frames_in = |ms: u32| ((u64::from(pcm.sample_rate) * u64::from(ms) / 1000) as usize).max(1);
+        let (block, step) = (frames_in(BLOCK_MS), frames_in(STEP_MS));
+        let mut powers = Vec::new();
+        let mut start = 0;
+        while start + block <= pcm.frames() {
+            let mut sums = vec![0.0; channels];
+            for frame in samples[start * channels..(start + block) * channels].chunks(channels) {
+                for (sum, sample) in sums.iter_mut().zip(frame) {
+                    *sum += sample * sample;
+                }
+            }
+            powers.push(sums.iter().zip(weights.iter()).map(|(sum, weight)| weight * sum / block as f64).sum::<f64>());
+            start += step;
+        }
+        integrate(&powers) as f32
+    }
+
+    /// Measure how loud the sound clip is over time, for drawing a loudness meter
+    ///
+    /// The clip is split into windows `window_ms` long that start every half window, so they
+    /// overlap by half. Each point is the time a window starts and the RMS level of the window in
+    /// decibels relative to full scale, with every channel counted together; silent windows
+    /// are negative infinity. The last windows are cut short by the end of the clip. Unlike
+    /// `loudness_k_weighted`, the frequencies aren't weighted.
+    ///
+    /// Only available on desktop with the `dsp` feature.
+    pub fn compute_loudness_curve(&self, window_ms: u32) -> Vec<(Duration, f32)> {
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let window = ((u64::from(pcm.sample_rate) * u64::from(window_ms) / 1000) as usize).max(2);
+        let hop = window / 2;
+        (0..pcm.frames().div_ceil(hop))
+            .map(|index| {
+                let start = index * hop;
+                let samples = &pcm.samples[start * channels..(start + window).min(pcm.frames()) * channels];
+                let power = samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32;
+                (pcm.duration_of(start), amplitude_to_db(power.sqrt()))
+            })
+            .collect()
+    }
+}
+
+// The two stages of the K-weighting filter at a given sample rate: a high shelf that models the
+// acoustic effect of the head, and a high-pass filter (the revised low-frequency B-curve)
+fn k_weighting(sample_rate: f64) -> (Biquad, Biquad) {
+    let shelf = {
+        let frequency = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * frequency / sample_rate).tan();
+        let high = 10f64.powf(gain_db / 20.0);
+        let band = high.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Biquad::new(
+            [(high + band * k / q + k * k) / a0, 2.0 * (k * k - high) / a0, (high - band * k / q + k * k) / a0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]
+        )
+    };
+    let high_pass = {
+        let frequency = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * frequency / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0])
+    };
+    (shelf, high_pass)
+}
+
+// How much each channel contributes to the loudness, assuming 5.1 for six channels
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) => 1.41,
+        _ => 1.0
+    }
+}
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+// Average the block powers that pass both the absolute and the relative gate
+fn integrate(powers: &[f64]) -> f64 {
+    let mean = |powers: &[f64]| powers.iter().sum::<f64>() / powers.len() as f64;
+    let loud: Vec<f64> = powers.iter().cloned().filter(|&power| to_lufs(power) > ABSOLUTE_GATE_LUFS).collect();
+    if loud.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+    let threshold = to_lufs(mean(&loud)) + RELATIVE_GATE_LU;
+    let gated: Vec<f64> = loud.into_iter().filter(|&power| to_lufs(power) > threshold).collect();
+    to_lufs(mean(&gated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, seconds: u32, channels: u16) -> Sound {
+        let samples: Vec<f32> = (0..48000 * seconds)
+            .flat_map(|index| {
+                let sample = amplitude * (2.0 * std::f32::consts::PI * 1000.0 * index as f32 / 48000.0).sin();
+                std::iter::repeat_n(sample, channels as usize)
+            })
+            .collect();
+        Sound::from_pcm(&samples, 48000, channels).unwrap()
+    }
+
+    #[test]
+    fn reference_levels() {
+        // EBU Tech 3341: a 1kHz stereo sine at -23 dBFS measures -23 LUFS
+        let stereo = sine(10f32.powf(-23.0 / 20.0), 5, 2).loudness_k_weighted();
+        assert!((stereo + 23.0).abs() < 0.1, "{}", stereo);
+        // BS.1770: a full-scale 1kHz sine in one channel measures -3.01 LUFS
+        let mono = sine(1.0, 5, 1).loudness_k_weighted();
+        assert!((mono + 3.01).abs() < 0.1, "{}", mono);
+    }
+
+    #[test]
+    fn loudness_curve() {
+        let curve = sine(0.5, 1, 2).compute_loudness_curve(100);
+        assert_eq!(curve.len(), 20);
+        assert_eq!(curve[1].0, Duration::from_millis(50));
+        // A sine's RMS level is 3dB below its peak
+        assert!(curve.iter().all(|&(_, level)| (level - amplitude_to_db(0.5) + 3.01).abs() < 0.05));
+        let silence = sine(0.0, 1, 1).compute_loudness_curve(300);
+        assert_eq!(silence.len(), 7);
+        assert_eq!(silence[0].1, f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn silent_or_short() {
+        assert_eq!(sine(0.0, 1, 2).loudness_k_weighted(), f32::NEG_INFINITY);
+        assert_eq!(Sound::from_pcm(&[0.5; 100], 48000, 1).unwrap().loudness_k_weighted(), f32::NEG_INFINITY);
+        // At 5Hz a block is two frames and a step is less than one, which must still finish
+        Sound::from_pcm(&[0.5; 20], 5, 1).unwrap().loudness_k_weighted();
+    }
+}
@@ -0,0 +1,328 @@
+// The sound data isn't available on the web, so only desktop parses metadata
+#![cfg_attr(target_arch="wasm32", allow(dead_code))]
+
+use crate::sound::format::{self, AudioFormat};
+use std::time::Duration;
+
+/// Information embedded in a sound file, such as the title and the artist
+///
+/// Each piece of information is only present if the file declares it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SoundMetadata {
+    /// The title of the track
+    pub title: Option<String>,
+    /// The artist who made the track
+    pub artist: Option<String>,
+    /// How long the track plays for
+    pub duration: Option<Duration>,
+    /// The bitrate of the encoded audio, in bits per second
+    pub bitrate: Option<u32>
+}
+
+// Read the metadata from OGG Vorbis, FLAC, or MP3 data, or None for any other format
+pub(super) fn parse(data: &[u8]) -> Option<SoundMetadata> {
+    match format::detect(data)? {
+        AudioFormat::Ogg => parse_ogg(data),
+        AudioFormat::Flac => parse_flac(data),
+        AudioFormat::Mp3 => Some(parse_mp3(data)),
+        AudioFormat::Wav => None
+    }
+}
+
+// A cursor over little-endian binary data that gives up at the end of the data
+pub(super) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, position: 0 }
+    }
+
+    pub(super) fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+        Some(bytes)
+    }
+
+    pub(super) fn u16_le(&mut self) -> Option<u16> {
+        self.take(2).map(|bytes| u16::from(bytes[0]) | u16::from(bytes[1]) << 8)
+    }
+
+    pub(super) fn u32_le(&mut self) -> Option<u32> {
+        self.take(4).map(|bytes| u32::from(bytes[0]) | u32::from(bytes[1]) << 8
+            | u32::from(bytes[2]) << 16 | u32::from(bytes[3]) << 24)
+    }
+}
+
+fn u24_be(bytes: &[u8]) -> usize {
+    (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize
+}
+
+// Fill in the title and the artist from a Vorbis comment block, which both OGG and FLAC use
+fn read_vorbis_comments(data: &[u8], metadata: &mut SoundMetadata) -> Option<()> {
+    let mut reader = Reader::new(data);
+    let vendor_length = reader.u32_le()? as usize;
+    reader.take(vendor_length)?;
+    for _ in 0..reader.u32_le()? {
+        let length = reader.u32_le()? as usize;
+        let comment = String::from_utf8_lossy(reader.take(length)?);
+        let mut parts = comment.splitn(2, '=');
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key, value.to_owned()),
+            _ => continue
+        };
+        if key.eq_ignore_ascii_case("TITLE") {
+            metadata.title = Some(value);
+        } else if key.eq_ignore_ascii_case("ARTIST") {
+            metadata.artist = Some(value);
+        }
+    }
+    Some(())
+}
+
+// Collect the first few packets of an OGG stream, skipping the page checksums
+fn ogg_packets(data: &[u8], count: usize) -> Option<Vec<Vec<u8>>> {
+    let mut packets = Vec::new();
+    let mut packet = Vec::new();
+    let mut page = 0;
+    while packets.len() < count {
+        if !data.get(page..)?.starts_with(b"OggS") {
+            return None;
+        }
+        let segments = *data.get(page + 26)? as usize;
+        let table = data.get(page + 27..page + 27 + segments)?;
+        let mut body = page + 27 + segments;
+        for &length in table {
+            packet.extend_from_slice(data.get(body..body + length as usize)?);
+            body += length as usize;
+            if length < 255 {
+                packets.push(std::mem::take(&mut packet));
+            }
+        }
+        page = body;
+    }
+    packets.truncate(count);
+    Some(packets)
+}
+
+fn parse_ogg(data: &[u8]) -> Option<SoundMetadata> {
+    let mut metadata = SoundMetadata::default();
+    let packets = ogg_packets(data, 2)?;
+    let mut identification = Reader::new(&packets[0]);
+    if identification.take(7)? != b"\x01vorbis" {
+        return None;
+    }
+    identification.take(5)?;
+    let sample_rate = identification.u32_le()?;
+    identification.take(4)?;
+    let nominal_bitrate = identification.u32_le()? as i32;
+    if nominal_bitrate > 0 {
+        metadata.bitrate = Some(nominal_bitrate as u32);
+    }
+    if packets[1].starts_with(b"\x03vorbis") {
+        read_vorbis_comments(&packets[1][7..], &mut metadata);
+    }
+    // The granule position of the last page is the number of samples in the stream
+    let last_page = data.windows(4).rposition(|window| window == b"OggS")?;
+    let mut granule = Reader::new(data.get(last_page + 6..last_page + 14)?);
+    let granule = u64::from(granule.u32_le()?) | u64::from(granule.u32_le()?) << 32;
+    if sample_rate > 0 && granule != u64::MAX {
+        metadata.duration = Some(Duration::from_secs_f64(granule as f64 / f64::from(sample_rate)));
+    }
+    Some(metadata)
+}
+
+fn parse_flac(data: &[u8]) -> Option<SoundMetadata> {
+    let mut metadata = SoundMetadata::default();
+    let mut reader = Reader::new(data);
+    reader.take(4)?;
+    loop {
+        let header = reader.take(4)?;
+        let block = reader.take(u24_be(&header[1..]))?;
+        match header[0] & 0x7F {
+            0 if block.len() >= 18 => {
+                let sample_rate = (block[10] as u32) << 12 | (block[11] as u32) << 4 | (block[12] as u32) >> 4;
+                let samples = u64::from(block[13] & 0x0F) << 32 | u64::from(block[14]) << 24
+                    | u64::from(block[15]) << 16 | u64::from(block[16]) << 8 | u64::from(block[17]);
+                if sample_rate > 0 && samples > 0 {
+                    let seconds = samples as f64 / f64::from(sample_rate);
+                    metadata.duration = Some(Duration::from_secs_f64(seconds));
+                    metadata.bitrate = Some((data.len() as f64 * 8.0 / seconds) as u32);
+                }
+            }
+            4 => {
+                read_vorbis_comments(block, &mut metadata);
+            }
+            _ => ()
+        }
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+    Some(metadata)
+}
+
+fn parse_mp3(data: &[u8]) -> SoundMetadata {
+    let mut metadata = SoundMetadata::default();
+    let audio = match data.get(..10) {
+        Some(header) if header.starts_with(b"ID3") => {
+            let size = syncsafe(&header[6..10]);
+            if header[3] == 3 || header[3] == 4 {
+                if let Some(frames) = data.get(10..10 + size) {
+                    read_id3_frames(frames, header[3], &mut metadata);
+                }
+            }
+            data.get(10 + size..).unwrap_or(&[])
+        }
+        _ => data
+    };
+    metadata.bitrate = mp3_bitrate(audio);
+    metadata
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |size, &byte| size << 7 | (byte & 0x7F) as usize)
+}
+
+fn read_id3_frames(mut frames: &[u8], version: u8, metadata: &mut SoundMetadata) {
+    while frames.len() >= 10 && frames[0] != 0 {
+        let size = if version == 4 {
+            syncsafe(&frames[4..8])
+        } else {
+            frames[4..8].iter().fold(0, |size, &byte| size << 8 | byte as usize)
+        };
+        let content = match frames.get(10..10 + size) {
+            Some(content) => content,
+            None => return
+        };
+        match &frames[..4] {
+            b"TIT2" => metadata.title = id3_text(content),
+            b"TPE1" => metadata.artist = id3_text(content),
+            _ => ()
+        }
+        frames = &frames[10 + size..];
+    }
+}
+
+fn id3_text(content: &[u8]) -> Option<String> {
+    let (&encoding, text) = content.split_first()?;
+    let text = match encoding {
+        0 => text.iter().map(|&byte| byte as char).collect(),
+        1 | 2 => {
+            let big_endian = encoding == 2 || text.starts_with(&[0xFE, 0xFF]);
+            let text = if encoding == 1 && text.len() >= 2 { &text[2..] } else { text };
+            let units: Vec<u16> = text.chunks(2)
+                .filter(|unit| unit.len() == 2)
+                .map(|unit| if big_endian {
+                    u16::from(unit[0]) << 8 | u16::from(unit[1])
+                } else {
+                    u16::from(unit[1]) << 8 | u16::from(unit[0])
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(text).into_owned()
+    };
+    Some(text.trim_end_matches('\0').to_owned())
+}
+
+// The bitrate declared by an MPEG layer III frame header at the start of the data
+fn mp3_bitrate(data: &[u8]) -> Option<u32> {
+    const MPEG1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+    const MPEG2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    let header = data.get(..3)?;
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 || header[1] & 0x06 != 0x02 {
+        return None;
+    }
+    let table = if header[1] & 0x18 == 0x18 { &MPEG1 } else { &MPEG2 };
+    match table.get((header[2] >> 4) as usize) {
+        Some(&kbps) if kbps > 0 => Some(kbps * 1000),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vorbis_comment_block(comments: &[&str]) -> Vec<u8> {
+        let mut block = vec![4, 0, 0, 0];
+        block.extend_from_slice(b"test");
+        block.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            block.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            block.extend_from_slice(comment.as_bytes());
+        }
+        block
+    }
+
+    #[test]
+    fn flac() {
+        let mut data = b"fLaC".to_vec();
+        // STREAMINFO: 44100 Hz, 88200 samples
+        data.extend_from_slice(&[0, 0, 0, 34]);
+        let mut info = [0u8; 34];
+        info[10] = 0x0A;
+        info[11] = 0xC4;
+        info[12] = 0x40;
+        info[15] = 0x01;
+        info[16] = 0x58;
+        info[17] = 0x88;
+        data.extend_from_slice(&info);
+        let comments = vorbis_comment_block(&["title=Theme", "ARTIST=Someone"]);
+        data.extend_from_slice(&[0x84, 0, 0, comments.len() as u8]);
+        data.extend_from_slice(&comments);
+        let metadata = parse(&data).unwrap();
+        assert_eq!(metadata.title, Some("Theme".to_owned()));
+        assert_eq!(metadata.artist, Some("Someone".to_owned()));
+        assert_eq!(metadata.duration, Some(Duration::from_secs(2)));
+        assert_eq!(metadata.bitrate, Some(data.len() as u32 * 4));
+    }
+
+    #[test]
+    fn ogg() {
+        let mut identification = b"\x01vorbis".to_vec();
+        identification.extend_from_slice(&[0, 0, 0, 0, 2]);
+        identification.extend_from_slice(&48000u32.to_le_bytes());
+        identification.extend_from_slice(&0u32.to_le_bytes());
+        identification.extend_from_slice(&128000u32.to_le_bytes());
+        identification.extend_from_slice(&[0, 0, 0, 0, 0xB8, 1]);
+        let mut comments = b"\x03vorbis".to_vec();
+        comments.extend_from_slice(&vorbis_comment_block(&["TITLE=Overworld"]));
+        comments.push(1);
+        let mut data = b"OggS".to_vec();
+        data.extend_from_slice(&[0, 2]);
+        data.extend_from_slice(&96000u64.to_le_bytes());
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&[2, identification.len() as u8, comments.len() as u8]);
+        data.extend_from_slice(&identification);
+        data.extend_from_slice(&comments);
+        let metadata = parse(&data).unwrap();
+        assert_eq!(metadata.title, Some("Overworld".to_owned()));
+        assert_eq!(metadata.artist, None);
+        assert_eq!(metadata.duration, Some(Duration::from_secs(2)));
+        assert_eq!(metadata.bitrate, Some(128000));
+    }
+
+    #[test]
+    fn id3() {
+        let mut frames = Vec::new();
+        frames.extend_from_slice(b"TIT2\0\0\0\x06\0\0\0Boss!");
+        frames.extend_from_slice(b"TPE1\0\0\0\x05\0\0\x03Band");
+        let mut data = b"ID3\x03\0\0\0\0\0".to_vec();
+        data.push(frames.len() as u8);
+        data.extend_from_slice(&frames);
+        data.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        let metadata = parse(&data).unwrap();
+        assert_eq!(metadata.title, Some("Boss!".to_owned()));
+        assert_eq!(metadata.artist, Some("Band".to_owned()));
+        assert_eq!(metadata.bitrate, Some(128000));
+    }
+
+    #[test]
+    fn wav() {
+        assert_eq!(parse(b"RIFF\0\0\0\0WAVE"), None);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::f64::consts::PI;
+
+impl Sound {
+    /// Make the volume of the sound clip pulse with a tremolo, returning the changed clip
+    ///
+    /// The volume is swept down and back up `rate_hz` times a second, between 0 and 20. `depth`
+    /// is how far the volume dips, between 0 and 1: at 1 it dips all the way to silence, and at
+    /// 0 the clip is unchanged.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn apply_tremolo(&self, rate_hz: f32, depth: f32) -> Result<Sound> {
+        if !(rate_hz > 0.0 && rate_hz <= 20.0) {
+            return Err(SoundError::InvalidParameter(format!("The tremolo rate must be above 0 and at most 20 Hz, not {}", rate_hz)).into());
+        }
+        if !(0.0..=1.0).contains(&depth) {
+            return Err(SoundError::InvalidParameter(format!("The tremolo depth must be between 0 and 1, not {}", depth)).into());
+        }
+        let pcm = self.pcm();
+        let mut samples = pcm.samples.clone();
+        for (frame, samples) in samples.chunks_mut(pcm.channels as usize).enumerate() {
+            let time = frame as f64 / f64::from(pcm.sample_rate);
+            let gain = 1.0 - depth * (1.0 + (2.0 * PI * f64::from(rate_hz) * time).sin() as f32) / 2.0;
+            for sample in samples {
+                *sample *= gain;
+            }
+        }
+        self.with_samples(&samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tremolo() {
+        let sound = Sound::from_pcm(&[0.5; 2000], 1000, 1).unwrap();
+        let pulsing = sound.apply_tremolo(2.0, 1.0).unwrap();
+        let samples = &pulsing.pcm().samples;
+        // The first valley is a quarter of a cycle in
+        assert!(samples[125].abs() < 1e-4);
+        assert!(samples[625].abs() < 1e-4);
+        assert!((samples[375] - 0.5).abs() < 1e-3);
+        let shallow = sound.apply_tremolo(2.0, 0.5).unwrap();
+        assert!((shallow.pcm().samples[125] - 0.25).abs() < 1e-3);
+        assert!(sound.apply_tremolo(0.0, 0.5).is_err());
+        assert!(sound.apply_tremolo(2.0, 1.5).is_err());
+    }
+}
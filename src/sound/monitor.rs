@@ -0,0 +1,103 @@
+use crate::{
+    Result,
+    sound::{Sound, StopHandle}
+};
+use rodio::source::Source;
+use std::time::Duration;
+
+// How many times a second the monitor callback is called
+const UPDATES_PER_SECOND: u32 = 60;
+
+impl Sound {
+    /// Play the sound clip, passing the samples to `callback` as they are played
+    ///
+    /// The callback is given the samples of roughly the last sixtieth of a second, interleaved by
+    /// channel and with the volume applied, so it can drive a level meter or an oscilloscope. The
+    /// callback runs on the audio thread, so it has to be quick; anything slow can make the
+    /// sound stutter. A common approach is to copy the samples somewhere the game loop can read
+    /// them.
+    ///
+    /// Only available on desktop, with the `monitor` feature.
+    pub fn monitor_output<F: Fn(&[f32]) + Send + Sync + 'static>(&self, callback: F) -> Result<StopHandle> {
+        self.play_controlled(|source| Box::new(Monitor::new(source, callback)))
+    }
+}
+
+// Passes a source through unchanged, calling a function with each window of samples
+struct Monitor<S, F> {
+    inner: S,
+    callback: F,
+    window: Vec<f32>,
+    length: usize
+}
+
+impl<S: Source<Item = f32>, F: Fn(&[f32])> Monitor<S, F> {
+    fn new(inner: S, callback: F) -> Monitor<S, F> {
+        let frames = (inner.sample_rate() / UPDATES_PER_SECOND).max(1) as usize;
+        let length = frames * inner.channels() as usize;
+        Monitor {
+            inner,
+            callback,
+            window: Vec::with_capacity(length),
+            length
+        }
+    }
+}
+
+impl<S: Source<Item = f32>, F: Fn(&[f32])> Iterator for Monitor<S, F> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next();
+        match sample {
+            Some(sample) => self.window.push(sample),
+            None if self.window.is_empty() => return None,
+            // The last, partial window is passed on when the source ends
+            None => ()
+        }
+        if self.window.len() >= self.length || sample.is_none() {
+            (self.callback)(&self.window);
+            self.window.clear();
+        }
+        sample
+    }
+}
+
+impl<S: Source<Item = f32>, F: Fn(&[f32])> Source for Monitor<S, F> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn windows() {
+        let windows = Arc::new(Mutex::new(Vec::new()));
+        let recorded = windows.clone();
+        let samples: Vec<f32> = (0..250).map(|index| index as f32).collect();
+        let monitor = Monitor::new(SamplesBuffer::new(2, 6000, samples.clone()), move |window: &[f32]| {
+            recorded.lock().unwrap().push(window.to_vec());
+        });
+        assert_eq!(monitor.collect::<Vec<f32>>(), samples);
+        let windows = windows.lock().unwrap();
+        assert_eq!(windows.iter().map(Vec::len).collect::<Vec<_>>(), vec![200, 50]);
+        assert_eq!(windows.concat(), samples);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::{iter, time::Duration};
+
+impl Sound {
+    /// Add silence to the start and the end of the sound clip, returning the padded clip
+    ///
+    /// `pre` is the silence before the clip starts and `post` is the silence after it ends. The
+    /// lengths are rounded to the nearest frame.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn mix_in_silence(&self, pre: Duration, post: Duration) -> Result<Sound> {
+        let pcm = self.pcm();
+        let samples_in = |duration: Duration| {
+            (duration.as_secs_f64() * f64::from(pcm.sample_rate)).round() as usize * pcm.channels as usize
+        };
+        let samples: Vec<f32> = iter::repeat_n(0.0, samples_in(pre))
+            .chain(pcm.samples.iter().cloned())
+            .chain(iter::repeat_n(0.0, samples_in(post)))
+            .collect();
+        self.with_samples(&samples)
+    }
+
+    /// Insert silence into the middle of the sound clip, returning the changed clip
+    ///
+    /// The clip is split at `position`, and `silence` worth of silence is put between the two
+    /// halves. Both lengths are rounded to the nearest frame. Fails if the position is past the
+    /// end of the clip.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn insert_silence_at(&self, position: Duration, silence: Duration) -> Result<Sound> {
+        let pcm = self.pcm();
+        let duration = pcm.duration_of(pcm.frames());
+        if position > duration {
+            return Err(SoundError::InvalidParameter(format!(
+                "The position ({:?}) is past the end of the sound ({:?})", position, duration)).into());
+        }
+        let frames_in = |duration: Duration| (duration.as_secs_f64() * f64::from(pcm.sample_rate)).round() as usize;
+        let split = frames_in(position).min(pcm.frames()) * pcm.channels as usize;
+        let samples: Vec<f32> = pcm.samples[..split].iter().cloned()
+            .chain(iter::repeat_n(0.0, frames_in(silence) * pcm.channels as usize))
+            .chain(pcm.samples[split..].iter().cloned())
+            .collect();
+        self.with_samples(&samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding() {
+        let sound = Sound::from_pcm(&[0.5, 0.5, 0.5, 0.5], 100, 2).unwrap();
+        let padded = sound.mix_in_silence(Duration::from_millis(30), Duration::from_millis(10)).unwrap();
+        let samples = &padded.pcm().samples;
+        assert_eq!(samples.len(), 12);
+        assert!(samples[..6].iter().all(|&sample| sample == 0.0));
+        assert!(samples[6..10].iter().all(|&sample| (sample - 0.5).abs() < 1e-3));
+        assert_eq!(&samples[10..], &[0.0, 0.0]);
+        assert_eq!(padded.analyze_silence(-40.0), (Duration::from_millis(30), Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn inserted_silence() {
+        let sound = Sound::from_pcm(&[0.5; 50], 100, 1).unwrap();
+        let ms = Duration::from_millis;
+        let gapped = sound.insert_silence_at(ms(200), ms(150)).unwrap();
+        assert_eq!(gapped.duration_or_zero(), sound.duration_or_zero() + ms(150));
+        let samples = &gapped.pcm().samples;
+        assert!(samples[19] > 0.49 && samples[35] > 0.49);
+        assert!(samples[20..35].iter().all(|&sample| sample == 0.0));
+        assert_eq!(sound.insert_silence_at(ms(500), ms(10)).unwrap().pcm().samples.len(), 51);
+        assert!(sound.insert_silence_at(ms(510), ms(10)).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use crate::{
+    Result,
+    sound::SoundError
+};
+use std::{io::Cursor, time::Duration};
+use crate::sound::Sound;
+#[cfg(not(target_arch="wasm32"))]
+use {
+    rodio::{decoder::Decoder, source::Source},
+    std::{
+        fmt,
+        sync::{Arc, OnceLock}
+    }
+};
+
+#[cfg(target_arch="wasm32")]
+use {
+    std::sync::OnceLock,
+    stdweb::unstable::TryInto
+};
+
+/// The decoded samples of a sound clip, created by `Sound::to_pcm_buffer`
+///
+/// Only available on desktop.
+#[cfg(not(target_arch="wasm32"))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PcmBuffer {
+    /// The samples, interleaved by channel, without the volume applied
+    pub samples: Vec<f32>,
+    /// The number of frames per second
+    pub sample_rate: u32,
+    /// The number of channels
+    pub channels: u16
+}
+
+#[cfg(not(target_arch="wasm32"))]
+impl PcmBuffer {
+    /// Get the number of frames in the buffer, each with one sample for every channel
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    // Average the channels of every frame together
+    pub(super) fn to_mono(&self) -> Vec<f32> {
+        let channels = self.channels as usize;
+        self.samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    }
+
+    // The time it takes to play the given number of frames
+    pub(super) fn duration_of(&self, frames: usize) -> Duration {
+        Duration::from_nanos(frames as u64 * 1_000_000_000 / u64::from(self.sample_rate))
+    }
+}
+
+// The decoded samples of a sound, filled in the first time they are needed
+//
+// Clones of a sound share the cache, because they share the same encoded data
+#[cfg(not(target_arch="wasm32"))]
+#[derive(Clone, Default)]
+pub(super) struct PcmCache(Arc<OnceLock<Arc<PcmBuffer>>>);
+
+#[cfg(not(target_arch="wasm32"))]
+impl fmt::Debug for PcmCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PcmCache").field("decoded", &self.0.get().is_some()).finish()
+    }
+}
+
+#[cfg(not(target_arch="wasm32"))]
+impl Sound {
+    // Decode the stored data, without applying the volume
+    pub(super) fn pcm(&self) -> Arc<PcmBuffer> {
+        self.decoded.0.get_or_init(|| {
+            let decoder = Decoder::new(Cursor::new(self.clone()))
+                .expect("Sound data is validated when the Sound is created");
+            let channels = decoder.channels();
+            let sample_rate = decoder.sample_rate();
+            Arc::new(PcmBuffer {
+                samples: decoder.convert_samples().collect(),
+                sample_rate,
+                channels
+            })
+        }).clone()
+    }
+
+    // The number of samples in the clip, without decoding it unless nothing else knows
+    //
+    // Clips that were already decoded and WAV files give the exact count; other formats estimate
+    // it from the duration in their metadata
+    pub(super) fn sample_count_hint(&self) -> usize {
+        if let Some(pcm) = self.decoded.0.get() {
+            return pcm.samples.len();
+        }
+        if let Ok(decoder) = Decoder::new(Cursor::new(self.clone())) {
+            if let (_, Some(count)) = decoder.size_hint() {
+                return count;
+            }
+            if let Some(duration) = self.metadata.as_ref().and_then(|metadata| metadata.duration) {
+                let frames = (duration.as_secs_f64() * f64::from(decoder.sample_rate())).round() as usize;
+                return frames * decoder.channels() as usize;
+            }
+        }
+        self.pcm().samples.len()
+    }
+
+    /// Get all of the decoded samples of the sound clip, without the volume applied
+    ///
+    /// The clip is decoded the first time this is called, and the buffer is shared by every
+    /// later call and every clone of the sound, so it's cheap to call again. Only available on
+    /// desktop.
+    pub fn to_pcm_buffer(&self) -> Result<Arc<PcmBuffer>> {
+        Ok(self.pcm())
+    }
+
+    /// Get the decoded samples of a single channel, without the volume applied
+    ///
+    /// For stereo sounds, channel 0 is the left channel and channel 1 is the right. Fails if the
+    /// sound doesn't have the given channel. The clip is decoded the first time it is needed and
+    /// kept in memory afterwards. Only available on desktop.
+    pub fn channel_data(&self, channel: u16) -> Result<Vec<f32>> {
+        let pcm = self.pcm();
+        if channel >= pcm.channels {
+            return Err(SoundError::InvalidParameter(format!(
+                "The sound has {} channels, so there is no channel {}", pcm.channels, channel)).into());
+        }
+        Ok(pcm.samples.iter().skip(channel as usize).step_by(pcm.channels as usize).cloned().collect())
+    }
+
+    // The channel count and sample rate, read from the header if the clip isn't decoded yet
+    fn format(&self) -> (u16, u32) {
+        match self.decoded.0.get() {
+            Some(pcm) => (pcm.channels, pcm.sample_rate),
+            None => {
+                let decoder = Decoder::new(Cursor::new(self.clone()))
+                    .expect("Sound data is validated when the Sound is created");
+                (decoder.channels(), decoder.sample_rate())
+            }
+        }
+    }
+
+    /// Get the number of channels in the sound clip
+    ///
+    /// Only available on desktop.
+    pub fn channels(&self) -> u16 {
+        self.format().0
+    }
+
+    /// Get the number of frames per second in the sound clip
+    ///
+    /// Only available on desktop.
+    pub fn sample_rate(&self) -> u32 {
+        self.format().1
+    }
+
+    /// Check if the sound clip has exactly two channels
+    ///
+    /// Only available on desktop.
+    pub fn is_stereo(&self) -> bool {
+        self.channels() == 2
+    }
+
+    /// Check if the sound clip has a single channel
+    ///
+    /// Only available on desktop.
+    pub fn is_mono(&self) -> bool {
+        self.channels() == 1
+    }
+}
+
+#[cfg(not(target_arch="wasm32"))]
+impl Sound {
+    // Create a sound from processed samples in the same format as this one, keeping its settings
+    #[cfg(feature = "dsp")]
+    pub(super) fn with_samples(&self, samples: &[f32]) -> Result<Sound> {
+        self.with_samples_in(samples, self.pcm().channels)
+    }
+
+    // Like with_samples, but with a different number of channels
+    //
+    // Only the data is replaced, so every playback setting is carried over, including ones added
+    // later
+    #[cfg(feature = "dsp")]
+    pub(super) fn with_samples_in(&self, samples: &[f32], channels: u16) -> Result<Sound> {
+        let encoded = Sound::from_pcm(samples, self.pcm().sample_rate, channels)?;
+        Ok(Sound {
+            val: encoded.val,
+            metadata: encoded.metadata,
+            decoded: encoded.decoded,
+            #[cfg(feature = "fingerprint")]
+            fingerprint: encoded.fingerprint,
+            ..self.clone()
+        })
+    }
+}
+
+impl Sound {
+    /// Read a single decoded sample of the sound clip, without playing it
+    ///
+    /// The index counts samples interleaved by channel, and the volume is not applied. The clip
+    /// is decoded the first time this is called and kept in memory afterwards, so scrubbing
+    /// through a waveform is cheap. Returns None if the index is past the end of the clip.
+    ///
+    /// Sounds can't be decoded on the web, so this always returns None there.
+    pub fn sample_at(&self, index: usize) -> Option<f32> {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.pcm().samples.get(index).cloned()
+        }
+        #[cfg(target_arch="wasm32")] {
+            let _ = index;
+            None
+        }
+    }
+
+    /// Get how long the sound clip takes to play once, or zero if it isn't known
+    ///
+    /// On the web the length is reported by the browser, which doesn't know it until enough of
+    /// the file has loaded, and has no length at all for live streams. This returns zero in both
+    /// cases, so it's always safe to call.
+    pub fn duration_or_zero(&self) -> Duration {
+        #[cfg(not(target_arch="wasm32"))] {
+            let pcm = self.pcm();
+            pcm.duration_of(pcm.frames())
+        }
+        #[cfg(target_arch="wasm32")] {
+            let seconds: f64 = js!( return @{&self.sound}.duration; ).try_into().unwrap_or(0.0);
+            if seconds.is_finite() && seconds > 0.0 {
+                Duration::from_secs_f64(seconds)
+            } else {
+                Duration::ZERO
+            }
+        }
+    }
+
+    /// Get the number of samples in the sound clip, counting every channel
+    ///
+    /// This is the number of frames times the number of channels, which is what a buffer for all
+    /// of the decoded samples has to hold. On desktop the count is exact; the clip is decoded the
+    /// first time it is needed and kept in memory afterwards.
+    ///
+    /// The browser doesn't give the sample rate or channel count of a file on the web. There the
+    /// count is estimated from the duration, assuming two channels at the rate the browser plays
+    /// audio at, and is 0 if the duration isn't known yet.
+    pub fn total_sample_count(&self) -> u64 {
+        #[cfg(not(target_arch="wasm32"))] {
+            self.pcm().samples.len() as u64
+        }
+        #[cfg(target_arch="wasm32")] {
+            (self.duration_or_zero().as_secs_f64() * output_sample_rate() * 2.0).round() as u64
+        }
+    }
+}
+
+// The rate the browser plays audio at, read from the page's audio context the first time it's needed
+#[cfg(target_arch="wasm32")]
+fn output_sample_rate() -> f64 {
+    static RATE: OnceLock<f64> = OnceLock::new();
+    *RATE.get_or_init(|| js!( return @{super::audio_context()}.sampleRate; ).try_into().unwrap_or(0.0))
+}
+
+// Convert a level in decibels relative to full scale into a linear amplitude
+#[cfg(not(target_arch="wasm32"))]
+pub(super) fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+// Convert a linear amplitude into a level in decibels relative to full scale
+#[cfg(not(target_arch="wasm32"))]
+pub(super) fn amplitude_to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.log10()
+}
+
+// Samples are stored as 16-bit integers because that is the only WAV encoding rodio can decode
+pub(super) fn encode_wav(samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>> {
+    if channels == 0 {
+        return Err(SoundError::InvalidParameter("A sound must have at least one channel".to_owned()).into());
+    }
+    if sample_rate == 0 {
+        return Err(SoundError::InvalidParameter("A sound must have a non-zero sample rate".to_owned()).into());
+    }
+    if !samples.len().is_multiple_of(channels as usize) {
+        return Err(SoundError::InvalidParameter(format!(
+            "{} samples cannot be split evenly between {} channels", samples.len(), channels)).into());
+    }
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int
+    };
+    let mut bytes = Cursor::new(Vec::new());
+    {
+        let mut writer = hound::WavWriter::new(&mut bytes, spec)?;
+        for &sample in samples {
+            writer.write_sample(f32_to_i16(sample))?;
+        }
+        writer.finalize()?;
+    }
+    Ok(bytes.into_inner())
+}
+
+// The inverse of the conversion rodio applies when it decodes 16-bit samples
+pub(super) fn f32_to_i16(sample: f32) -> i16 {
+    let sample = sample.clamp(-1.0, 1.0);
+    if sample >= 0.0 {
+        (sample * i16::MAX as f32).round() as i16
+    } else {
+        (-sample * i16::MIN as f32).round() as i16
+    }
+}
+
+// White noise between -1 and 1 from a linear congruential generator, for testing the effects
+#[cfg(all(test, feature = "dsp", not(target_arch="wasm32")))]
+pub(super) fn noise(length: usize, seed: u32) -> Vec<f32> {
+    let mut state = seed;
+    (0..length)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+        })
+        .collect()
+}
+
+#[cfg(all(test, not(target_arch="wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples() {
+        let sound = Sound::from_pcm(&[0.0, 0.5, -0.5, 1.0], 44100, 2).unwrap();
+        assert_eq!(sound.sample_at(0), Some(0.0));
+        assert!((sound.sample_at(2).unwrap() + 0.5).abs() < 1e-4);
+        assert!((sound.clone().sample_at(3).unwrap() - 1.0).abs() < 1e-4);
+        assert_eq!(sound.sample_at(4), None);
+    }
+
+    #[test]
+    fn duration() {
+        let sound = Sound::from_pcm(&[0.0; 8], 4, 2).unwrap();
+        assert_eq!(sound.duration_or_zero(), Duration::from_secs(1));
+        assert_eq!(Sound::from_pcm(&[], 44100, 1).unwrap().duration_or_zero(), Duration::ZERO);
+        assert_eq!(sound.total_sample_count(), 8);
+    }
+
+    #[test]
+    fn format() {
+        let stereo = Sound::from_pcm(&[0.0; 8], 22050, 2).unwrap();
+        assert!(stereo.is_stereo() && !stereo.is_mono());
+        assert_eq!(stereo.sample_rate(), 22050);
+        stereo.pcm();
+        assert_eq!(stereo.channels(), 2);
+        let mono = Sound::from_pcm(&[0.0; 8], 44100, 1).unwrap();
+        assert!(mono.is_mono() && !mono.is_stereo());
+        assert!(!Sound::from_pcm(&[0.0; 9], 44100, 3).unwrap().is_stereo());
+    }
+
+    #[test]
+    fn buffer() {
+        let sound = Sound::from_pcm(&[0.0, 0.5, -0.5, 0.0], 100, 2).unwrap();
+        let buffer = sound.to_pcm_buffer().unwrap();
+        assert_eq!((buffer.sample_rate, buffer.channels, buffer.frames()), (100, 2, 2));
+        assert!(Arc::ptr_eq(&buffer, &sound.clone().to_pcm_buffer().unwrap()));
+    }
+
+    #[test]
+    fn channels() {
+        let silence = Sound::from_pcm(&[0.0; 8], 44100, 2).unwrap();
+        assert_eq!(silence.channel_data(0).unwrap(), vec![0.0; 4]);
+        assert_eq!(silence.channel_data(1).unwrap(), vec![0.0; 4]);
+        assert!(silence.channel_data(2).is_err());
+        let sound = Sound::from_pcm(&[0.0, 0.5, 0.0, 0.0, 0.5, 0.0], 44100, 3).unwrap();
+        assert!(sound.channel_data(1).unwrap().iter().all(|&sample| (sample - 0.5).abs() < 1e-4));
+        assert_eq!(sound.channel_data(1).unwrap().len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "dsp")]
+    fn processing_keeps_settings() {
+        use crate::sound::{LoopMode, LoopRegion, SoundFadeCurve};
+        let mut sound = Sound::from_pcm(&[0.25; 400], 100, 2).unwrap();
+        sound.set_volume(0.5);
+        sound.set_loop_sound(LoopMode::Repeat);
+        sound.set_loop_region(LoopRegion { start: Duration::from_millis(500), end: Duration::from_secs(1) }).unwrap();
+        sound.set_loop_count(3);
+        sound.set_fade_curve(SoundFadeCurve::EaseIn);
+        sound.set_start_gain(-6.0).set_end_gain(-12.0);
+        let quieter = sound.apply_gain(-6.0).unwrap();
+        assert_ne!(quieter.pcm(), sound.pcm());
+        assert_eq!(quieter.volume(), 0.5);
+        assert_eq!(quieter.loop_region(), sound.loop_region());
+        assert_eq!(quieter.loop_count(), 3);
+        assert_eq!(quieter.fade_curve(), SoundFadeCurve::EaseIn);
+        assert_eq!((quieter.start_gain, quieter.end_gain), (Some(-6.0), Some(-12.0)));
+        assert_eq!(quieter.loop_mode, LoopMode::Repeat);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, StopHandle}
+};
+use rand::{
+    FromEntropy, Rng,
+    rngs::SmallRng,
+    seq::SliceRandom
+};
+#[cfg(not(target_arch="wasm32"))]
+use {
+    crate::sound::{
+        pcm::PcmBuffer,
+        play_detached,
+        source::{Controlled, PlaybackControls}
+    },
+    rodio::source::Source,
+    std::{sync::Arc, time::Duration}
+};
+#[cfg(target_arch="wasm32")]
+use stdweb::Value;
+
+/// A list of sounds that are played one after another in a random order
+///
+/// Each sound in the list is played once before any is repeated. If the playlist loops, it is
+/// shuffled again after every pass, and the sound that was just played never plays twice in a
+/// row.
+#[derive(Clone, Debug)]
+pub struct ShuffledPlaylist {
+    sounds: Vec<Sound>,
+    rng: SmallRng,
+    loop_playlist: bool
+}
+
+impl ShuffledPlaylist {
+    /// Create a playlist of the given sounds, which plays through once or loops forever
+    pub fn new(sounds: Vec<Sound>, loop_playlist: bool) -> ShuffledPlaylist {
+        ShuffledPlaylist {
+            sounds,
+            rng: SmallRng::from_entropy(),
+            loop_playlist
+        }
+    }
+
+    /// Start playing the playlist
+    ///
+    /// The returned handle controls the whole playlist, and it finishes once the last sound does.
+    /// Each sound plays with its own volume, but its looping settings are ignored. Fails if the
+    /// playlist is empty.
+    ///
+    /// On the web, the playlist is shuffled again with the browser's random numbers when it
+    /// loops.
+    pub fn play(mut self) -> Result<StopHandle> {
+        if self.sounds.is_empty() {
+            return Err(SoundError::InvalidParameter("The playlist has no sounds to play".to_owned()).into());
+        }
+        let mut order: Vec<usize> = (0..self.sounds.len()).collect();
+        order.shuffle(&mut self.rng);
+        #[cfg(not(target_arch="wasm32"))] {
+            let controls = PlaybackControls::new();
+            let source = Playlist {
+                current: self.sounds[order[0]].pcm(),
+                volume: self.sounds[order[0]].volume,
+                index: 0,
+                order,
+                position: 0,
+                playlist: self
+            };
+            play_detached(Controlled::new(source, controls.clone()))?;
+            StopHandle::new(controls)
+        }
+        #[cfg(target_arch="wasm32")] {
+            let sources: Vec<Value> = self.sounds.iter().map(|sound| sound.sound.clone()).collect();
+            let volumes: Vec<f64> = self.sounds.iter().map(|sound| f64::from(sound.volume.max(0.0).min(1.0))).collect();
+            let order: Vec<u32> = order.into_iter().map(|index| index as u32).collect();
+            let sound = js! {
+                const sources = @{sources};
+                const volumes = @{volumes};
+                const shuffle = (order) => {
+                    for (let i = order.length - 1; i > 0; i--) {
+                        const j = Math.floor(Math.random() * (i + 1));
+                        [order[i], order[j]] = [order[j], order[i]];
+                    }
+                };
+                let order = @{order};
+                let index = 0;
+                const snd = sources[order[0]].cloneNode();
+                snd.volume = volumes[order[0]];
+                snd.addEventListener("ended", (event) => {
+                    if (snd.__stopping) { return; }
+                    index += 1;
+                    if (index >= order.length) {
+                        if (!@{self.loop_playlist}) { return; }
+                        const last = order[order.length - 1];
+                        shuffle(order);
+                        if (order.length > 1 && order[0] === last) {
+                            [order[0], order[1]] = [order[1], order[0]];
+                        }
+                        index = 0;
+                    }
+                    // Carry on with the next sound, without the handle seeing the end
+                    event.stopImmediatePropagation();
+                    snd.src = sources[order[index]].src;
+                    snd.volume = volumes[order[index]];
+                    snd.play();
+                });
+                snd.play();
+                return snd;
+            };
+            StopHandle::new(sound)
+        }
+    }
+}
+
+impl Sound {
+    /// Play a random sound from the given sounds
+    ///
+    /// Fails if there are no sounds to pick from. To keep playing random sounds one after
+    /// another, use a `ShuffledPlaylist`.
+    pub fn play_shuffled(sounds: &[Sound]) -> Result<StopHandle> {
+        match sounds.choose(&mut rand::thread_rng()) {
+            Some(sound) => sound.play(),
+            None => Err(SoundError::InvalidParameter("There are no sounds to pick from".to_owned()).into())
+        }
+    }
+}
+
+// Plays the sounds of a playlist in order, shuffling the order again between passes if it loops
+#[cfg(not(target_arch="wasm32"))]
+struct Playlist {
+    playlist: ShuffledPlaylist,
+    order: Vec<usize>,
+    index: usize,
+    current: Arc<PcmBuffer>,
+    volume: f32,
+    position: usize
+}
+
+#[cfg(not(target_arch="wasm32"))]
+impl Playlist {
+    fn advance(&mut self) -> bool {
+        self.index += 1;
+        if self.index >= self.order.len() {
+            if !self.playlist.loop_playlist {
+                return false;
+            }
+            let last = self.order[self.order.len() - 1];
+            self.order.shuffle(&mut self.playlist.rng);
+            if self.order.len() > 1 && self.order[0] == last {
+                let swap = self.playlist.rng.gen_range(1, self.order.len());
+                self.order.swap(0, swap);
+            }
+            self.index = 0;
+        }
+        let sound = &self.playlist.sounds[self.order[self.index]];
+        self.current = sound.pcm();
+        self.volume = sound.volume;
+        self.position = 0;
+        true
+    }
+}
+
+#[cfg(not(target_arch="wasm32"))]
+impl Iterator for Playlist {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Skip over any empty sounds, but give up after a full pass of nothing but empty sounds
+        let mut skipped = 0;
+        while self.position >= self.current.samples.len() {
+            if skipped > self.order.len() || !self.advance() {
+                return None;
+            }
+            skipped += 1;
+        }
+        let sample = self.current.samples[self.position];
+        self.position += 1;
+        Some(sample * self.volume)
+    }
+}
+
+#[cfg(not(target_arch="wasm32"))]
+impl Source for Playlist {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.current.samples.len() - self.position).filter(|&left| left > 0)
+    }
+
+    fn channels(&self) -> u16 {
+        self.current.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sound_once() {
+        let sounds = vec![
+            Sound::from_pcm(&[0.25; 2], 100, 1).unwrap(),
+            Sound::from_pcm(&[0.5; 2], 100, 1).unwrap(),
+            Sound::from_pcm(&[], 100, 1).unwrap(),
+            Sound::from_pcm(&[-0.5; 2], 100, 1).unwrap()
+        ];
+        let playlist = ShuffledPlaylist::new(sounds, false);
+        let mut order: Vec<usize> = (0..4).collect();
+        order.shuffle(&mut rand::thread_rng());
+        let source = Playlist {
+            current: playlist.sounds[order[0]].pcm(),
+            volume: 1.0,
+            index: 0,
+            order,
+            position: 0,
+            playlist
+        };
+        let mut samples: Vec<i32> = source.map(|sample| (sample * 4.0).round() as i32).collect();
+        samples.sort();
+        assert_eq!(samples, vec![-2, -2, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn looping_never_repeats() {
+        let sounds = vec![Sound::from_pcm(&[0.25], 100, 1).unwrap(), Sound::from_pcm(&[0.5], 100, 1).unwrap()];
+        let playlist = ShuffledPlaylist::new(sounds, true);
+        let source = Playlist {
+            current: playlist.sounds[0].pcm(),
+            volume: 1.0,
+            index: 0,
+            order: vec![0, 1],
+            position: 0,
+            playlist
+        };
+        let samples: Vec<f32> = source.take(20).collect();
+        assert!(samples.windows(2).all(|pair| (pair[0] - pair[1]).abs() > 0.1));
+        assert!(Sound::play_shuffled(&[]).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::{
+    f32::consts::PI,
+    time::Duration
+};
+
+// The range of pitches the detector looks for, in hertz
+const MIN_PITCH: u32 = 50;
+const MAX_PITCH: u32 = 1000;
+// The most samples the pitch is detected from, taken from the middle of the clip
+const ANALYSIS_LENGTH: usize = 16384;
+// The shortest clip that can be pitch corrected
+const MIN_AUTO_TUNE_MS: u64 = 100;
+// How well a clip has to correlate with itself a pitch period later to have a single clear pitch
+const MIN_CLARITY: f32 = 0.8;
+
+impl Sound {
+    /// Move the pitch of the sound clip by a number of semitones without changing its length
+    ///
+    /// Unlike `play_at_pitch`, the clip plays at its normal speed. This uses a simple form of
+    /// PSOLA: the pitch period of the clip is detected once, and windowed periods are overlapped
+    /// closer together or further apart. It works best on clips with a single, steady pitch,
+    /// like a held note or a voice line; clips without a clear pitch are processed in 10
+    /// millisecond grains. Fails if the pitch would move by more than an octave, where the
+    /// artifacts are too strong.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn pitch_shift_without_time_stretch(&self, semitones: i32) -> Result<Sound> {
+        if semitones.abs() > 12 {
+            return Err(SoundError::InvalidParameter(format!(
+                "The pitch can move by at most 12 semitones, not {}", semitones)).into());
+        }
+        let pcm = self.pcm();
+        let period = pitch_period(&pcm.to_mono(), pcm.sample_rate)
+            .unwrap_or(pcm.sample_rate as usize / 100)
+            .max(1);
+        self.overlap_periods(period, 2f64.powf(f64::from(semitones) / 12.0))
+    }
+
+    /// Snap the pitch of the sound clip to `target_pitch_hz` without changing its length
+    ///
+    /// This is a toy form of pitch correction, for effects like a robotic voice. The pitch of the
+    /// clip is detected once, and the whole clip is moved by the same amount with the method of
+    /// `pitch_shift_without_time_stretch`, so it works for a single held note rather than a
+    /// melody. Fails if the clip is shorter than 100 milliseconds, if it doesn't have a single
+    /// clear pitch, such as a chord or noise, or if the pitch would move by more than an octave.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn auto_tune(&self, target_pitch_hz: f32) -> Result<Sound> {
+        if !(target_pitch_hz > 0.0 && target_pitch_hz.is_finite()) {
+            return Err(SoundError::InvalidParameter(format!("The target pitch must be above 0Hz, not {}Hz", target_pitch_hz)).into());
+        }
+        let pcm = self.pcm();
+        if pcm.duration_of(pcm.frames()) < Duration::from_millis(MIN_AUTO_TUNE_MS) {
+            return Err(SoundError::InvalidParameter(format!(
+                "A clip must be at least {}ms long to detect its pitch", MIN_AUTO_TUNE_MS)).into());
+        }
+        let samples = pcm.to_mono();
+        let period = pitch_period(&samples, pcm.sample_rate)
+            .filter(|&period| correlation(analysis_window(&samples), period) >= MIN_CLARITY)
+            .ok_or_else(|| SoundError::InvalidParameter("The clip doesn't have a single clear pitch".to_owned()))?;
+        let ratio = f64::from(target_pitch_hz) * period as f64 / f64::from(pcm.sample_rate);
+        if !(0.5..=2.0).contains(&ratio) {
+            return Err(SoundError::InvalidParameter(format!(
+                "The pitch of {}Hz can move by at most an octave, not to {}Hz",
+                f64::from(pcm.sample_rate) / period as f64, target_pitch_hz)).into());
+        }
+        self.overlap_periods(period, ratio)
+    }
+
+    // Overlap windowed pitch periods of the clip to multiply its pitch by the ratio
+    fn overlap_periods(&self, period: usize, ratio: f64) -> Result<Sound> {
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let frames = pcm.frames();
+        let window: Vec<f32> = (0..2 * period)
+            .map(|index| 0.5 - 0.5 * (PI * index as f32 / period as f32).cos())
+            .collect();
+        let mut output = vec![0f32; pcm.samples.len()];
+        let mut weights = vec![0f32; frames];
+        let spacing = period as f64 / ratio;
+        let mut mark = 0.0;
+        while mark < (frames + period) as f64 {
+            // Each output period is copied from the input period nearest to it
+            let source = (mark / period as f64).round() as usize * period;
+            let target = mark.round() as usize;
+            // The window is centered on the marks
+            for (offset, weight) in window.iter().enumerate() {
+                let (from, to) = match ((source + offset).checked_sub(period), (target + offset).checked_sub(period)) {
+                    (Some(from), Some(to)) if from < frames && to < frames => (from, to),
+                    _ => continue
+                };
+                weights[to] += weight;
+                for channel in 0..channels {
+                    output[to * channels + channel] += pcm.samples[from * channels + channel] * weight;
+                }
+            }
+            mark += spacing;
+        }
+        for (frame, weight) in output.chunks_mut(channels).zip(weights.iter()) {
+            if *weight > 1e-3 {
+                for sample in frame.iter_mut() {
+                    *sample /= weight;
+                }
+            }
+        }
+        self.with_samples(&output)
+    }
+}
+
+// Find the pitch period of mono samples, in samples, from their autocorrelation
+//
+// The shortest period that correlates almost as well as the best one is chosen, so a multiple of
+// the true period isn't mistaken for it. Returns None if nothing correlates well enough.
+fn pitch_period(samples: &[f32], sample_rate: u32) -> Option<usize> {
+    let samples = analysis_window(samples);
+    let (min_lag, max_lag) = ((sample_rate / MAX_PITCH) as usize, (sample_rate / MIN_PITCH) as usize);
+    let max_lag = max_lag.min(samples.len() / 2);
+    if min_lag < 1 || min_lag >= max_lag {
+        return None;
+    }
+    let correlations: Vec<f32> = (min_lag..=max_lag)
+        .map(|lag| correlation(samples, lag))
+        .collect();
+    let best = correlations.iter().cloned().fold(0.0, f32::max);
+    if best < 0.5 {
+        return None;
+    }
+    (1..correlations.len() - 1)
+        .find(|&index| {
+            let value = correlations[index];
+            value >= 0.9 * best && value >= correlations[index - 1] && value >= correlations[index + 1]
+        })
+        .map(|index| index + min_lag)
+}
+
+// The samples the pitch is detected from
+fn analysis_window(samples: &[f32]) -> &[f32] {
+    let start = samples.len().saturating_sub(ANALYSIS_LENGTH) / 2;
+    &samples[start..samples.len().min(start + ANALYSIS_LENGTH)]
+}
+
+// The normalized autocorrelation of mono samples at a lag
+fn correlation(samples: &[f32], lag: usize) -> f32 {
+    let (head, tail) = (&samples[..samples.len() - lag], &samples[lag..]);
+    let product: f32 = head.iter().zip(tail).map(|(a, b)| a * b).sum();
+    let energy = head.iter().map(|a| a * a).sum::<f32>() * tail.iter().map(|b| b * b).sum::<f32>();
+    if energy > 0.0 { product / energy.sqrt() } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A tone with its first five harmonics, like a voice or an instrument
+    fn tone(frequency: f32, sample_rate: u32, seconds: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * seconds) as usize)
+            .map(|index| {
+                let phase = 2.0 * PI * frequency * index as f32 / sample_rate as f32;
+                (1..=5).map(|harmonic| 0.1 * (phase * harmonic as f32).sin() / harmonic as f32).sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detect_pitch() {
+        assert_eq!(pitch_period(&tone(200.0, 8000, 0.5), 8000), Some(40));
+        assert_eq!(pitch_period(&[0.0; 4000], 8000), None);
+    }
+
+    #[test]
+    fn shift() {
+        let sound = Sound::from_pcm(&tone(200.0, 8000, 0.5), 8000, 1).unwrap();
+        let up = sound.pitch_shift_without_time_stretch(12).unwrap();
+        assert_eq!(up.pcm().frames(), sound.pcm().frames());
+        assert_eq!(pitch_period(&up.pcm().samples, 8000), Some(20));
+        let down = sound.pitch_shift_without_time_stretch(-5).unwrap();
+        assert_eq!(pitch_period(&down.pcm().samples, 8000), Some(53));
+        assert!(sound.pitch_shift_without_time_stretch(13).is_err());
+        assert!(sound.pitch_shift_without_time_stretch(-12).is_ok());
+    }
+
+    #[test]
+    fn auto_tune() {
+        let sound = Sound::from_pcm(&tone(200.0, 8000, 0.5), 8000, 1).unwrap();
+        let tuned = sound.auto_tune(250.0).unwrap();
+        assert_eq!(tuned.pcm().frames(), sound.pcm().frames());
+        assert_eq!(pitch_period(&tuned.pcm().samples, 8000), Some(32));
+        let chord: Vec<f32> = tone(200.0, 8000, 0.5).iter().zip(tone(317.0, 8000, 0.5)).map(|(a, b)| a + b).collect();
+        assert!(Sound::from_pcm(&chord, 8000, 1).unwrap().auto_tune(250.0).is_err());
+        assert!(Sound::from_pcm(&tone(200.0, 8000, 0.05), 8000, 1).unwrap().auto_tune(250.0).is_err());
+        assert!(sound.auto_tune(500.0).is_err());
+        assert!(sound.auto_tune(0.0).is_err());
+    }
+}
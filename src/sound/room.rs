@@ -0,0 +1,233 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, StopHandle}
+};
+use rodio::source::Source;
+use rustfft::{
+    FFT, FFTplanner,
+    num_complex::Complex,
+    num_traits::Zero
+};
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::Duration
+};
+
+// The number of frames convolved at a time, and the length of each part of the impulse response
+const BLOCK_FRAMES: usize = 512;
+
+/// The acoustics of a room, described by how it echoes a single click
+///
+/// The impulse response is the sound of the room after a single full-scale click, as mono
+/// samples. It should be recorded at the same sample rate as the sounds played in the room; it
+/// isn't resampled. Cloning a room model shares the impulse response. Only available on
+/// desktop.
+#[derive(Clone, Debug)]
+pub struct RoomModel {
+    impulse_response: Arc<Vec<f32>>
+}
+
+impl RoomModel {
+    /// Create a room model from the samples of an impulse response
+    pub fn new(impulse_response: Arc<Vec<f32>>) -> RoomModel {
+        RoomModel { impulse_response }
+    }
+
+    /// Create a room model from a recorded impulse response, such as one loaded with `Sound::load`
+    ///
+    /// The channels of the recording are averaged together, and its volume is not applied.
+    pub fn from_sound(sound: &Sound) -> RoomModel {
+        RoomModel::new(Arc::new(sound.pcm().to_mono()))
+    }
+
+    /// Get the samples of the impulse response
+    pub fn impulse_response(&self) -> &[f32] {
+        &self.impulse_response
+    }
+}
+
+impl Sound {
+    /// Play the sound clip as if it were playing in the given room
+    ///
+    /// The sound is convolved with the room's impulse response as it plays, so it echoes like
+    /// the room. Only the echoed sound is heard; an impulse response usually starts with the
+    /// direct sound anyway. The echoes keep playing for the length of the impulse response after
+    /// the clip ends. Fails if the impulse response is empty.
+    ///
+    /// The convolution is split into blocks so long impulse responses stay cheap, but it is still
+    /// much more work than playing the clip normally. Only available on desktop.
+    pub fn play_with_room_acoustics(&self, room: RoomModel) -> Result<StopHandle> {
+        if room.impulse_response.is_empty() {
+            return Err(SoundError::InvalidParameter("The impulse response of a room can't be empty".to_owned()).into());
+        }
+        self.play_controlled(|source| Box::new(Convolver::new(source, &room.impulse_response)))
+    }
+}
+
+// The input and the recent spectra of one channel
+struct ChannelState {
+    input: Vec<Complex<f32>>,
+    spectra: VecDeque<Vec<Complex<f32>>>
+}
+
+// Convolves a source with an impulse response, using uniformly partitioned overlap-save
+//
+// Each block of input is transformed together with the previous block, multiplied with the
+// transform of every part of the impulse response it overlaps, and transformed back.
+struct Convolver<S> {
+    inner: S,
+    fft: Arc<dyn FFT<f32>>,
+    inverse: Arc<dyn FFT<f32>>,
+    partitions: Vec<Vec<Complex<f32>>>,
+    channels: Vec<ChannelState>,
+    output: Vec<f32>,
+    position: usize,
+    // The blocks still to play after the input ends, for the echoes to die out
+    tail: usize
+}
+
+impl<S: Source<Item = f32>> Convolver<S> {
+    fn new(inner: S, impulse_response: &[f32]) -> Convolver<S> {
+        let size = 2 * BLOCK_FRAMES;
+        let fft = FFTplanner::new(false).plan_fft(size);
+        let inverse = FFTplanner::new(true).plan_fft(size);
+        let partitions = impulse_response.chunks(BLOCK_FRAMES)
+            .map(|part| {
+                let mut input: Vec<Complex<f32>> = part.iter().map(|&sample| Complex::new(sample, 0.0)).collect();
+                input.resize(size, Complex::zero());
+                let mut spectrum = vec![Complex::zero(); size];
+                fft.process(&mut input, &mut spectrum);
+                spectrum
+            })
+            .collect();
+        let channels = (0..inner.channels())
+            .map(|_| ChannelState {
+                input: vec![Complex::zero(); size],
+                spectra: VecDeque::new()
+            })
+            .collect();
+        Convolver {
+            inner,
+            fft,
+            inverse,
+            partitions,
+            channels,
+            output: Vec::new(),
+            position: 0,
+            tail: 0
+        }
+    }
+
+    // Convolve the next block of input, returning false once the echoes have died out
+    fn process_block(&mut self) -> bool {
+        let channels = self.channels.len();
+        let block: Vec<f32> = self.inner.by_ref().take(BLOCK_FRAMES * channels).collect();
+        if block.is_empty() {
+            if self.tail == 0 {
+                return false;
+            }
+            self.tail -= 1;
+        } else {
+            self.tail = self.partitions.len() - 1;
+        }
+        let size = 2 * BLOCK_FRAMES;
+        self.output.clear();
+        self.output.resize(BLOCK_FRAMES * channels, 0.0);
+        self.position = 0;
+        for (channel, state) in self.channels.iter_mut().enumerate() {
+            state.input.rotate_left(BLOCK_FRAMES);
+            for (frame, input) in state.input[BLOCK_FRAMES..].iter_mut().enumerate() {
+                *input = Complex::new(block.get(frame * channels + channel).cloned().unwrap_or(0.0), 0.0);
+            }
+            let mut input = state.input.clone();
+            let mut spectrum = vec![Complex::zero(); size];
+            self.fft.process(&mut input, &mut spectrum);
+            state.spectra.push_front(spectrum);
+            state.spectra.truncate(self.partitions.len());
+            let mut sum = vec![Complex::zero(); size];
+            for (spectrum, partition) in state.spectra.iter().zip(self.partitions.iter()) {
+                for ((sum, x), h) in sum.iter_mut().zip(spectrum.iter()).zip(partition.iter()) {
+                    *sum += x * h;
+                }
+            }
+            let mut result = vec![Complex::zero(); size];
+            self.inverse.process(&mut sum, &mut result);
+            for (frame, sample) in result[BLOCK_FRAMES..].iter().enumerate() {
+                self.output[frame * channels + channel] = sample.re / size as f32;
+            }
+        }
+        true
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Convolver<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.output.len() && !self.process_block() {
+            return None;
+        }
+        let sample = self.output[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Convolver<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels.len() as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn convolution() {
+        let input: Vec<f32> = (0..1500).map(|index| (index as f32 * 0.01).sin()).collect();
+        let mut impulse_response = vec![0.0; 700];
+        impulse_response[0] = 0.5;
+        impulse_response[600] = 0.25;
+        let output: Vec<f32> = Convolver::new(SamplesBuffer::new(1, 44100, input.clone()), &impulse_response).collect();
+        // Three blocks of input, and one more for the echoes
+        assert_eq!(output.len(), 4 * BLOCK_FRAMES);
+        for (index, sample) in output.iter().enumerate() {
+            let direct = input.get(index).map_or(0.0, |sample| sample * 0.5);
+            let echo = index.checked_sub(600).and_then(|index| input.get(index)).map_or(0.0, |sample| sample * 0.25);
+            assert!((sample - direct - echo).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn separate_channels() {
+        let output: Vec<f32> = Convolver::new(SamplesBuffer::new(2, 44100, vec![1.0, 0.0, 0.0, 1.0]), &[1.0, 0.5]).collect();
+        let expected = [1.0, 0.0, 0.5, 1.0, 0.0, 0.5];
+        for (sample, expected) in output.iter().zip(expected.iter()) {
+            assert!((sample - expected).abs() < 1e-5);
+        }
+        assert!(output[6..].iter().all(|sample| sample.abs() < 1e-5));
+    }
+
+    #[test]
+    fn empty_room() {
+        let sound = Sound::from_pcm(&[0.0; 4], 44100, 1).unwrap();
+        assert!(sound.play_with_room_acoustics(RoomModel::new(Arc::new(Vec::new()))).is_err());
+        let room = RoomModel::from_sound(&Sound::from_pcm(&[0.5, 0.0, 0.5, 1.0], 44100, 2).unwrap());
+        assert_eq!(room.impulse_response().len(), 2);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, StopHandle, pcm::{amplitude_to_db, db_to_amplitude}}
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering}
+    },
+    thread::{self, JoinHandle},
+    time::Duration
+};
+
+// How often the compressor re-evaluates the side-chain level
+const UPDATE_MS: u32 = 10;
+// How often a ducked sound is adjusted to the sound ducking it
+const DUCK_UPDATE_MS: u64 = 50;
+
+/// Automatically lowers the volume of one sound while another is loud, known as ducking
+///
+/// A typical use is lowering the music while a voice line plays: the voice is the side-chain and
+/// the music is the target. A background thread measures the loudness of the side-chain and, when
+/// it goes above the threshold, reduces the target's volume according to the compression ratio.
+/// The attack and release times control how quickly the target is ducked and how quickly it
+/// recovers.
+///
+/// The compressor takes ownership of both stop handles. It stops adjusting the volume once either
+/// sound finishes; use `into_handles` to get the handles back. Dropping the compressor drops the
+/// handles, which stops both sounds. Only available on desktop.
+pub struct SideChainCompressor {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<(StopHandle, StopHandle)>>
+}
+
+impl SideChainCompressor {
+    /// Start ducking the target whenever the side-chain is louder than `threshold_db`
+    ///
+    /// The threshold is relative to full scale, and the ratio is how many decibels the side-chain
+    /// must rise above the threshold for the target to be lowered by one decibel. Fails if the
+    /// ratio is below 1.
+    pub fn new(sidechain: StopHandle, mut target: StopHandle, threshold_db: f32, ratio: f32,
+            attack_ms: u32, release_ms: u32) -> Result<SideChainCompressor> {
+        if ratio.is_nan() || ratio < 1.0 {
+            return Err(SoundError::InvalidParameter(format!("A compression ratio must be at least 1, not {}", ratio)).into());
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let thread = thread::spawn(move || {
+            let base_volume = target.volume();
+            let attack = smoothing_factor(attack_ms);
+            let release = smoothing_factor(release_ms);
+            let mut reduction_db = 0.0;
+            while thread_running.load(Ordering::SeqCst) && !sidechain.is_finished() && !target.is_finished() {
+                let level_db = amplitude_to_db(sidechain.level());
+                let wanted_db = gain_reduction_db(level_db, threshold_db, ratio);
+                let factor = if wanted_db > reduction_db { attack } else { release };
+                reduction_db += (wanted_db - reduction_db) * factor;
+                target.set_volume(base_volume * db_to_amplitude(-reduction_db));
+                thread::sleep(Duration::from_millis(u64::from(UPDATE_MS)));
+            }
+            target.set_volume(base_volume);
+            (sidechain, target)
+        });
+        Ok(SideChainCompressor {
+            running,
+            thread: Some(thread)
+        })
+    }
+
+    /// Stop ducking, restore the target's volume, and return the side-chain and target handles
+    pub fn into_handles(mut self) -> (StopHandle, StopHandle) {
+        self.finish().expect("The handles are only taken once")
+    }
+
+    fn finish(&mut self) -> Option<(StopHandle, StopHandle)> {
+        self.running.store(false, Ordering::SeqCst);
+        self.thread.take().map(|thread| thread.join().expect("The ducking thread panicked"))
+    }
+}
+
+impl Drop for SideChainCompressor {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl Sound {
+    /// Play the sound clip, lowering its volume whenever the `ducking_sound` is loud
+    ///
+    /// A background thread checks the most recent samples of the ducking sound every 50
+    /// milliseconds, and sets the volume of the new sound to `1 - amount * rms`, where `rms` is
+    /// the RMS amplitude of those samples. An amount of 0 never lowers the sound, and 1 silences
+    /// it while the ducking sound is at full scale. The thread stops once either sound finishes,
+    /// and the volume goes back to normal. Setting the volume of the returned handle has no
+    /// effect until then. Fails unless the amount is between 0 and 1.
+    ///
+    /// This is simpler than a `SideChainCompressor`, which has a threshold and smooths the
+    /// changes in volume, but doesn't take ownership of the ducking sound's handle. Only
+    /// available on desktop.
+    pub fn play_ducked(&self, ducking_sound: &StopHandle, amount: f32) -> Result<StopHandle> {
+        if !(0.0..=1.0).contains(&amount) {
+            return Err(SoundError::InvalidParameter(format!("The ducking amount must be between 0 and 1, not {}", amount)).into());
+        }
+        let handle = self.play()?;
+        let ducker = ducking_sound.controls.clone();
+        let ducked = handle.controls.clone();
+        thread::spawn(move || {
+            let base_volume = ducked.volume();
+            while !ducker.is_finished() && !ducked.is_finished() && !ducked.is_stopped() {
+                let gain = ducking_gain(ducker.snapshot().as_deref().unwrap_or(&[]), ducker.volume(), amount);
+                ducked.set_volume(base_volume * gain);
+                thread::sleep(Duration::from_millis(DUCK_UPDATE_MS));
+            }
+            ducked.set_volume(base_volume);
+        });
+        Ok(handle)
+    }
+}
+
+// The volume of a ducked sound, from the latest samples of the sound ducking it
+fn ducking_gain(samples: &[f32], volume: f32, amount: f32) -> f32 {
+    if samples.is_empty() {
+        return 1.0;
+    }
+    let rms = (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt() * volume;
+    (1.0 - amount * rms).max(0.0)
+}
+
+// How far the reduction moves towards its target each update, for the given time constant
+fn smoothing_factor(time_ms: u32) -> f32 {
+    if time_ms == 0 {
+        1.0
+    } else {
+        1.0 - (-(UPDATE_MS as f32) / time_ms as f32).exp()
+    }
+}
+
+// The static compression curve: how much to lower the target for a given side-chain level
+pub(super) fn gain_reduction_db(level_db: f32, threshold_db: f32, ratio: f32) -> f32 {
+    if level_db > threshold_db {
+        (level_db - threshold_db) * (1.0 - 1.0 / ratio)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_curve() {
+        assert_eq!(gain_reduction_db(-30.0, -20.0, 4.0), 0.0);
+        assert_eq!(gain_reduction_db(f32::NEG_INFINITY, -20.0, 4.0), 0.0);
+        assert_eq!(gain_reduction_db(-12.0, -20.0, 4.0), 6.0);
+        assert_eq!(gain_reduction_db(-12.0, -20.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn ducking() {
+        assert_eq!(ducking_gain(&[], 1.0, 1.0), 1.0);
+        assert_eq!(ducking_gain(&[0.5, -0.5], 1.0, 1.0), 0.5);
+        assert_eq!(ducking_gain(&[0.5, -0.5], 0.5, 0.5), 0.875);
+        assert_eq!(ducking_gain(&[1.0; 4], 2.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn smoothing() {
+        assert_eq!(smoothing_factor(0), 1.0);
+        assert!(smoothing_factor(10) > smoothing_factor(100));
+        assert!(smoothing_factor(1000) > 0.0);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::sound::{Sound, spectrum::Stft};
+
+// The analysis frames are this many samples long, and start this many samples apart
+const FRAME_SIZE: usize = 2048;
+const HOP_SIZE: usize = FRAME_SIZE / 2;
+
+impl Sound {
+    /// Measure how alike the frequencies of two sound clips are, from 0 to 1
+    ///
+    /// The average spectrum of each clip is compared with the cosine similarity, so clips with
+    /// the same balance of frequencies score close to 1 even if their waveforms differ, and
+    /// clips with nothing in common score 0. Timing isn't compared, and neither is loudness:
+    /// a clip played backwards or at half the volume has the same spectrum. This is useful for
+    /// finding near-duplicate sounds in a large library.
+    ///
+    /// The spectra are compared bin by bin, so both clips should have the same sample rate.
+    /// Returns 0 if either clip is silent. Only available on desktop with the `dsp` feature.
+    pub fn compare_spectrum(&self, other: &Sound) -> f32 {
+        let (a, b) = (average_spectrum(self), average_spectrum(other));
+        let dot: f32 = a.iter().zip(b.iter()).map(|(a, b)| a * b).sum();
+        let length = |spectrum: &[f32]| spectrum.iter().map(|value| value * value).sum::<f32>().sqrt();
+        let lengths = length(&a) * length(&b);
+        if lengths > 0.0 { (dot / lengths).min(1.0) } else { 0.0 }
+    }
+}
+
+// The magnitude of each frequency bin, averaged over the frames of the clip
+fn average_spectrum(sound: &Sound) -> Vec<f32> {
+    let samples = sound.pcm().to_mono();
+    let mut stft = Stft::new(FRAME_SIZE);
+    let mut spectrum = vec![0.0; FRAME_SIZE / 2 + 1];
+    let frames = samples.len().div_ceil(HOP_SIZE);
+    for frame in 0..frames {
+        for (total, magnitude) in spectrum.iter_mut().zip(stft.magnitudes(&samples, frame * HOP_SIZE)) {
+            *total += magnitude / frames as f32;
+        }
+    }
+    spectrum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn sine(frequency: f32, volume: f32) -> Sound {
+        let samples: Vec<f32> = (0..8000).map(|index| volume * (2.0 * PI * frequency * index as f32 / 8000.0).sin()).collect();
+        Sound::from_pcm(&samples, 8000, 1).unwrap()
+    }
+
+    #[test]
+    fn similarity() {
+        let low = sine(220.0, 0.5);
+        assert!((low.compare_spectrum(&low) - 1.0).abs() < 1e-4);
+        assert!(low.compare_spectrum(&sine(220.0, 0.1)) > 0.99);
+        assert!(low.compare_spectrum(&low.reverse()) > 0.99);
+        assert!(low.compare_spectrum(&sine(2000.0, 0.5)) < 0.05);
+        let silence = Sound::from_pcm(&[0.0; 100], 8000, 1).unwrap();
+        assert_eq!(low.compare_spectrum(&silence), 0.0);
+    }
+}
@@ -0,0 +1,942 @@
+use crate::sound::{
+    Envelope,
+    SoundFadeCurve,
+    events::{EventBus, PlayEvent, PlayEventReceiver},
+    pcm::{PcmBuffer, db_to_amplitude}
+};
+use rodio::source::Source;
+use std::{
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}
+    },
+    time::Duration
+};
+
+// How often a controlled source publishes a new level
+const METER_WINDOW_MS: u32 = 10;
+// The number of samples in each published snapshot of the waveform
+const SNAPSHOT_SAMPLES: usize = 1024;
+
+// The state shared between a playing source and the handle that controls it
+//
+// The floats are stored as their bits so they can be read and written from the main thread
+// without locking the audio thread
+#[derive(Debug)]
+pub(super) struct PlaybackControls {
+    // The RMS amplitude of the most recent window of samples, before the volume is applied
+    level: AtomicU32,
+    volume: AtomicU32,
+    // The number of whole frames that have been played
+    frames: AtomicU64,
+    // The number of whole frames played since the source last looped
+    position: AtomicU64,
+    // The sample rate of the source, or 0 if it hasn't started playing
+    sample_rate: AtomicU32,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    // How long the sound takes to fade out once it is stopped, in nanoseconds
+    stop_fade: AtomicU64,
+    stop_curve: Mutex<SoundFadeCurve>,
+    finished: AtomicBool,
+    // The most recent window of samples, before the volume is applied
+    snapshot: RwLock<Vec<f32>>,
+    events: EventBus
+}
+
+impl PlaybackControls {
+    pub(super) fn new() -> Arc<PlaybackControls> {
+        Arc::new(PlaybackControls {
+            level: AtomicU32::new(0f32.to_bits()),
+            volume: AtomicU32::new(1f32.to_bits()),
+            frames: AtomicU64::new(0),
+            position: AtomicU64::new(0),
+            sample_rate: AtomicU32::new(0),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            stop_fade: AtomicU64::new(0),
+            stop_curve: Mutex::new(SoundFadeCurve::Linear),
+            finished: AtomicBool::new(false),
+            snapshot: RwLock::new(Vec::new()),
+            events: EventBus::default()
+        })
+    }
+
+    pub(super) fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn set_volume(&self, volume: f32) {
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    pub(super) fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn position(&self) -> Option<Duration> {
+        match self.sample_rate.load(Ordering::Relaxed) {
+            0 => None,
+            rate => Some(Duration::from_nanos(self.position.load(Ordering::Relaxed) * 1_000_000_000 / u64::from(rate)))
+        }
+    }
+
+    pub(super) fn set_paused(&self, paused: bool) {
+        if self.paused.swap(paused, Ordering::Relaxed) != paused && !self.is_finished() {
+            self.events.send(if paused { PlayEvent::Paused } else { PlayEvent::Resumed });
+        }
+    }
+
+    pub(super) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn subscribe(&self) -> PlayEventReceiver {
+        self.events.subscribe()
+    }
+
+    pub(super) fn set_stop_fade(&self, duration: Duration, curve: SoundFadeCurve) {
+        *self.stop_curve.lock().expect("The stop curve lock was poisoned") = curve;
+        self.stop_fade.store(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(super) fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn snapshot(&self) -> Option<Vec<f32>> {
+        let snapshot = self.snapshot.read().expect("The snapshot lock was poisoned");
+        Some(snapshot.clone()).filter(|samples| !samples.is_empty())
+    }
+}
+
+// The number of times a looping source has started a new pass
+//
+// The source counts its own passes, so the sources wrapped around it can tell where each pass
+// begins without knowing how long the clip is
+#[derive(Clone, Debug, Default)]
+pub(super) struct LoopCounter(Arc<AtomicU32>);
+
+impl LoopCounter {
+    pub(super) fn count(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Applies the requests from a handle and reports events back to it, while measuring how loud
+// the samples are
+pub(super) struct Controlled<S> {
+    inner: S,
+    controls: Arc<PlaybackControls>,
+    sum_squares: f32,
+    count: u32,
+    window: u32,
+    // The samples since the last snapshot was published
+    recent: Vec<f32>,
+    // The passes through the looping source, and how many of them have been reported
+    passes: Option<LoopCounter>,
+    loops: u32,
+    // The number of loops to stop after, or 0 to loop forever
+    loop_limit: u32,
+    played: usize,
+    // The length of the fade after a stop is requested, the frame it started on, and its shape
+    stop_fade: Option<(usize, usize, SoundFadeCurve)>
+}
+
+impl<S: Source<Item = f32>> Controlled<S> {
+    pub(super) fn new(inner: S, controls: Arc<PlaybackControls>) -> Controlled<S> {
+        let window = (inner.sample_rate() * u32::from(inner.channels()) * METER_WINDOW_MS / 1000).max(1);
+        Controlled {
+            inner,
+            controls,
+            sum_squares: 0.0,
+            count: 0,
+            window,
+            recent: Vec::with_capacity(SNAPSHOT_SAMPLES),
+            passes: None,
+            loops: 0,
+            loop_limit: 0,
+            played: 0,
+            stop_fade: None
+        }
+    }
+
+    // Report a loop each time the looping source starts a new pass
+    pub(super) fn counting_loops(mut self, passes: LoopCounter) -> Controlled<S> {
+        self.passes = Some(passes);
+        self
+    }
+
+    // Stop after this many loops, or never if the count is 0
+    pub(super) fn limit_loops(mut self, count: u32) -> Controlled<S> {
+        self.loop_limit = count;
+        self
+    }
+
+    // Whether the source has started a new pass since the last loop was reported
+    fn take_loop(&mut self) -> bool {
+        match self.passes.as_ref().map(LoopCounter::count) {
+            Some(passes) if passes != self.loops => {
+                self.loops = passes;
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn finish(&mut self) -> Option<f32> {
+        self.controls.level.store(0f32.to_bits(), Ordering::Relaxed);
+        self.controls.finished.store(true, Ordering::Relaxed);
+        self.controls.events.send(PlayEvent::Finished);
+        None
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Controlled<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.controls.is_finished() {
+            return None;
+        }
+        let channels = self.inner.channels() as usize;
+        let frame = self.played / channels;
+        let stopping = self.controls.stopped.load(Ordering::Relaxed);
+        if stopping && self.stop_fade.is_none() {
+            let seconds = self.controls.stop_fade.load(Ordering::Relaxed) as f64 / 1e9;
+            let curve = *self.controls.stop_curve.lock().expect("The stop curve lock was poisoned");
+            self.stop_fade = Some(((seconds * f64::from(self.inner.sample_rate())) as usize, frame, curve));
+        }
+        // A sound that is being stopped plays through its fade, even if it was paused
+        if self.controls.is_paused() && !stopping {
+            self.controls.level.store(0f32.to_bits(), Ordering::Relaxed);
+            return Some(0.0);
+        }
+        // The fade only ends between frames, so every channel gets the same fade
+        let fade = match self.stop_fade {
+            Some((length, start, _)) if frame - start >= length && self.played.is_multiple_of(channels) => return self.finish(),
+            Some((length, start, curve)) => 1.0 - curve.apply((frame - start) as f32 / length.max(1) as f32),
+            None => 1.0
+        };
+        let sample = match self.inner.next() {
+            Some(sample) => sample,
+            None => return self.finish()
+        };
+        if self.played == 0 {
+            self.controls.sample_rate.store(self.inner.sample_rate(), Ordering::Relaxed);
+            self.controls.events.send(PlayEvent::Started);
+        } else if self.take_loop() {
+            // The first sample of the pass after the last loop is never played
+            if self.loop_limit > 0 && self.loops >= self.loop_limit {
+                return self.finish();
+            }
+            self.controls.position.store(0, Ordering::Relaxed);
+            self.controls.events.send(PlayEvent::Looped);
+        }
+        self.played += 1;
+        if self.played.is_multiple_of(channels) {
+            self.controls.frames.fetch_add(1, Ordering::Relaxed);
+            self.controls.position.fetch_add(1, Ordering::Relaxed);
+        }
+        self.sum_squares += sample * sample;
+        self.count += 1;
+        if self.count >= self.window {
+            let level = (self.sum_squares / self.count as f32).sqrt();
+            self.controls.level.store(level.to_bits(), Ordering::Relaxed);
+            self.sum_squares = 0.0;
+            self.count = 0;
+        }
+        self.recent.push(sample);
+        if self.recent.len() >= SNAPSHOT_SAMPLES {
+            // The audio thread never waits for a reader; the snapshot is skipped instead
+            if let Ok(mut snapshot) = self.controls.snapshot.try_write() {
+                std::mem::swap(&mut *snapshot, &mut self.recent);
+            }
+            self.recent.clear();
+        }
+        Some(sample * self.controls.volume() * fade)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Controlled<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Ramps the volume of a source up from silence over its first few frames
+pub(super) struct FadeIn<S> {
+    inner: S,
+    // The length of the fade, and how far into it the source is, in samples
+    length: u64,
+    position: u64,
+    curve: SoundFadeCurve
+}
+
+impl<S: Source<Item = f32>> FadeIn<S> {
+    pub(super) fn new(inner: S, duration: Duration, curve: SoundFadeCurve) -> FadeIn<S> {
+        let samples_per_second = f64::from(inner.sample_rate()) * f64::from(inner.channels());
+        FadeIn {
+            length: (duration.as_secs_f64() * samples_per_second) as u64,
+            position: 0,
+            curve,
+            inner
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for FadeIn<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        if self.position >= self.length {
+            return Some(sample);
+        }
+        // Every channel of a frame gets the same gain
+        let frame = self.position - self.position % u64::from(self.inner.channels());
+        self.position += 1;
+        Some(sample * self.curve.apply(frame as f32 / self.length as f32))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for FadeIn<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Ramps the volume of a source of known length down to silence over its last few frames
+pub(super) struct FadeOut<S> {
+    inner: S,
+    // The length of the fade, and how many samples are left in the source
+    length: u64,
+    remaining: u64,
+    curve: SoundFadeCurve
+}
+
+impl<S: Source<Item = f32>> FadeOut<S> {
+    pub(super) fn new(inner: S, duration: Duration, total_samples: usize, curve: SoundFadeCurve) -> FadeOut<S> {
+        let samples_per_second = f64::from(inner.sample_rate()) * f64::from(inner.channels());
+        FadeOut {
+            length: (duration.as_secs_f64() * samples_per_second) as u64,
+            remaining: total_samples as u64,
+            curve,
+            inner
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for FadeOut<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        let channels = u64::from(self.inner.channels());
+        // Every channel of a frame gets the same gain
+        let frames_left = self.remaining.saturating_sub(1) / channels * channels;
+        self.remaining = self.remaining.saturating_sub(1);
+        if frames_left >= self.length {
+            Some(sample)
+        } else {
+            Some(sample * (1.0 - self.curve.apply(1.0 - frames_left as f32 / self.length as f32)))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for FadeOut<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Ramps the gain, in decibels, from one level to another over each pass through a source
+//
+// The lengths of the passes only set how fast the gain ramps, so they can be estimates; the ramp
+// holds at its end level if a pass runs long, and starts over whenever a new pass begins
+pub(super) struct GainRamp<S> {
+    inner: S,
+    start: f32,
+    end: f32,
+    // The number of samples in the first pass, and in each pass after it
+    lengths: (usize, usize),
+    passes: LoopCounter,
+    pass: u32,
+    position: usize
+}
+
+impl<S: Source<Item = f32>> GainRamp<S> {
+    pub(super) fn new(inner: S, start: f32, end: f32, lengths: (usize, usize), passes: LoopCounter) -> GainRamp<S> {
+        GainRamp {
+            inner,
+            start,
+            end,
+            lengths,
+            pass: passes.count(),
+            passes,
+            position: 0
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for GainRamp<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        let pass = self.passes.count();
+        if pass != self.pass {
+            self.pass = pass;
+            self.position = 0;
+        }
+        let channels = self.inner.channels() as usize;
+        let frame = self.position / channels;
+        let frames = if pass == 0 { self.lengths.0 } else { self.lengths.1 } / channels;
+        self.position += 1;
+        let progress = if frames > 1 { (frame as f32 / (frames - 1) as f32).min(1.0) } else { 0.0 };
+        Some(sample * db_to_amplitude(self.start + (self.end - self.start) * progress))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for GainRamp<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Follows an envelope with the volume of a source, by the time since it started playing
+pub(super) struct Automated<S> {
+    inner: S,
+    envelope: Envelope,
+    position: u64
+}
+
+impl<S: Source<Item = f32>> Automated<S> {
+    pub(super) fn new(inner: S, envelope: Envelope) -> Automated<S> {
+        Automated {
+            inner,
+            envelope,
+            position: 0
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Automated<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        let frame = self.position / u64::from(self.inner.channels());
+        self.position += 1;
+        Some(sample * self.envelope.volume_at(frame as f64 / f64::from(self.inner.sample_rate())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Automated<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Plays a source, then a fresh pass of it each time the last one ends, forever
+//
+// Each pass is made anew, so a sound that repeats can be decoded as it plays instead of being
+// kept in memory. A pass that has no samples at all ends the source instead.
+pub(super) struct Repeat<S> {
+    current: S,
+    next_pass: Box<dyn FnMut() -> S + Send>,
+    passes: LoopCounter
+}
+
+impl<S: Source<Item = f32>> Repeat<S> {
+    pub(super) fn new(first: S, next_pass: impl FnMut() -> S + Send + 'static) -> Repeat<S> {
+        Repeat {
+            current: first,
+            next_pass: Box::new(next_pass),
+            passes: LoopCounter::default()
+        }
+    }
+
+    pub(super) fn passes(&self) -> LoopCounter {
+        self.passes.clone()
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Repeat<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(sample) = self.current.next() {
+            return Some(sample);
+        }
+        self.current = (self.next_pass)();
+        let sample = self.current.next()?;
+        self.passes.increment();
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Repeat<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.current.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.current.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// Plays a buffer up to the end of a loop region, then repeats the region forever
+pub(super) struct RegionLoop {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+    loop_start: usize,
+    loop_end: usize,
+    index: usize,
+    passes: LoopCounter
+}
+
+impl RegionLoop {
+    pub(super) fn new(pcm: &PcmBuffer, volume: f32, loop_start: usize, loop_end: usize) -> RegionLoop {
+        RegionLoop {
+            samples: pcm.samples[..loop_end].iter().map(|sample| sample * volume).collect(),
+            sample_rate: pcm.sample_rate,
+            channels: pcm.channels,
+            loop_start,
+            loop_end,
+            index: 0,
+            passes: LoopCounter::default()
+        }
+    }
+
+    pub(super) fn passes(&self) -> LoopCounter {
+        self.passes.clone()
+    }
+}
+
+impl Iterator for RegionLoop {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.index == self.loop_end && self.loop_start < self.loop_end {
+            self.index = self.loop_start;
+            self.passes.increment();
+        }
+        let sample = self.samples.get(self.index).cloned();
+        self.index += 1;
+        sample
+    }
+}
+
+impl Source for RegionLoop {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// Plays a buffer forwards, then backwards, and so on forever
+//
+// The first and last frames are not repeated when the direction changes
+pub(super) struct PingPong {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: usize,
+    frame: usize,
+    channel: usize,
+    forwards: bool,
+    passes: LoopCounter
+}
+
+impl PingPong {
+    pub(super) fn new(pcm: &PcmBuffer, volume: f32) -> PingPong {
+        PingPong {
+            samples: pcm.samples.iter().map(|sample| sample * volume).collect(),
+            sample_rate: pcm.sample_rate,
+            channels: pcm.channels as usize,
+            frame: 0,
+            channel: 0,
+            forwards: true,
+            passes: LoopCounter::default()
+        }
+    }
+
+    pub(super) fn passes(&self) -> LoopCounter {
+        self.passes.clone()
+    }
+
+    fn advance_frame(&mut self) {
+        let frames = self.samples.len() / self.channels;
+        if frames < 2 {
+            return;
+        }
+        if self.forwards && self.frame + 1 == frames {
+            self.forwards = false;
+        } else if !self.forwards && self.frame == 0 {
+            self.forwards = true;
+        }
+        if self.forwards {
+            self.frame += 1;
+        } else {
+            self.frame -= 1;
+        }
+    }
+}
+
+impl Iterator for PingPong {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = *self.samples.get(self.frame * self.channels + self.channel)?;
+        // Each trip there and back again starts on the first frame, which is only played once
+        if self.frame == 0 && self.channel == 0 && !self.forwards {
+            self.passes.increment();
+        }
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.channel = 0;
+            self.advance_frame();
+        }
+        Some(sample)
+    }
+}
+
+impl Source for PingPong {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn controlled_volume_and_stop() {
+        let pcm = PcmBuffer {
+            samples: vec![0.5; 8],
+            sample_rate: 44100,
+            channels: 1
+        };
+        let controls = PlaybackControls::new();
+        let mut source = Controlled::new(PingPong::new(&pcm, 1.0), controls.clone());
+        assert_eq!(source.next(), Some(0.5));
+        controls.set_volume(0.5);
+        assert_eq!(source.next(), Some(0.25));
+        assert_eq!(controls.frames(), 2);
+        assert!(!controls.is_finished());
+        controls.stop();
+        assert_eq!(source.next(), None);
+        assert!(controls.is_finished());
+        assert_eq!(source.next(), None);
+    }
+
+    #[test]
+    fn snapshot() {
+        let pcm = PcmBuffer {
+            samples: (0..1500).map(|index| index as f32 / 1500.0).collect(),
+            sample_rate: 44100,
+            channels: 1
+        };
+        let controls = PlaybackControls::new();
+        let mut source = Controlled::new(PingPong::new(&pcm, 1.0), controls.clone());
+        source.by_ref().take(1000).for_each(drop);
+        assert_eq!(controls.snapshot(), None);
+        controls.set_volume(0.5);
+        source.by_ref().take(1100).for_each(drop);
+        assert_eq!(controls.snapshot(), Some(pcm.samples[1024..1500].iter().chain(pcm.samples.iter().rev().skip(1)).take(1024).cloned().collect()));
+    }
+
+    #[test]
+    fn controlled_events() {
+        use futures::{Future, Stream};
+        let pcm = PcmBuffer {
+            samples: vec![0.5; 4],
+            sample_rate: 44100,
+            channels: 2
+        };
+        let controls = PlaybackControls::new();
+        let events = controls.subscribe();
+        let looped = PingPong::new(&pcm, 1.0);
+        let passes = looped.passes();
+        let mut source = Controlled::new(looped, controls.clone()).counting_loops(passes);
+        for _ in 0..6 {
+            source.next();
+        }
+        controls.set_paused(true);
+        assert_eq!(source.next(), Some(0.0));
+        controls.set_paused(false);
+        controls.stop();
+        assert_eq!(source.next(), None);
+        use PlayEvent::*;
+        assert_eq!(events.collect().wait().unwrap(), vec![Started, Looped, Paused, Resumed, Finished]);
+    }
+
+    #[test]
+    fn fade_in() {
+        let pcm = PcmBuffer {
+            samples: vec![1.0; 8],
+            sample_rate: 2,
+            channels: 2
+        };
+        let faded: Vec<f32> = FadeIn::new(PingPong::new(&pcm, 1.0), Duration::from_secs(1), SoundFadeCurve::Linear).take(8).collect();
+        assert_eq!(faded, vec![0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0]);
+        let eased: Vec<f32> = FadeIn::new(PingPong::new(&pcm, 1.0), Duration::from_secs(1), SoundFadeCurve::EaseIn).take(8).collect();
+        assert_eq!(eased, vec![0.0, 0.0, 0.25, 0.25, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn fade_out() {
+        let pcm = PcmBuffer {
+            samples: vec![1.0; 8],
+            sample_rate: 2,
+            channels: 2
+        };
+        let faded: Vec<f32> = FadeOut::new(PingPong::new(&pcm, 1.0), Duration::from_secs(1), 8, SoundFadeCurve::Linear).take(8).collect();
+        assert_eq!(faded, vec![1.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.0, 0.0]);
+        let faded: Vec<f32> = FadeOut::new(PingPong::new(&pcm, 1.0), Duration::from_secs(2), 8, SoundFadeCurve::Linear).take(8).collect();
+        assert_eq!(faded, vec![0.75, 0.75, 0.5, 0.5, 0.25, 0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn gain_ramp() {
+        let pcm = PcmBuffer {
+            samples: vec![1.0; 6],
+            sample_rate: 2,
+            channels: 2
+        };
+        let buffer = move || SamplesBuffer::new(pcm.channels, pcm.sample_rate, pcm.samples.clone());
+        let repeated = Repeat::new(buffer(), buffer);
+        let passes = repeated.passes();
+        let ramped: Vec<f32> = GainRamp::new(repeated, 0.0, -40.0, (6, 6), passes).take(8).collect();
+        let expected = [1.0, 1.0, 0.1, 0.1, 0.01, 0.01, 1.0, 1.0];
+        for (sample, expected) in ramped.iter().zip(&expected) {
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn automated() {
+        let pcm = PcmBuffer {
+            samples: vec![1.0; 8],
+            sample_rate: 2,
+            channels: 2
+        };
+        let envelope = Envelope::new(vec![(Duration::ZERO, 0.0), (Duration::from_secs(1), 1.0)]);
+        let automated: Vec<f32> = Automated::new(PingPong::new(&pcm, 1.0), envelope).take(10).collect();
+        assert_eq!(automated, vec![0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn loop_limit() {
+        use futures::{Future, Stream};
+        let pcm = PcmBuffer {
+            samples: vec![0.25, 0.5, 0.75],
+            sample_rate: 3,
+            channels: 1
+        };
+        let controls = PlaybackControls::new();
+        let events = controls.subscribe();
+        let looped = PingPong::new(&pcm, 1.0);
+        let passes = looped.passes();
+        let source = Controlled::new(looped, controls.clone()).counting_loops(passes).limit_loops(2);
+        assert_eq!(source.collect::<Vec<f32>>(), vec![0.25, 0.5, 0.75, 0.5, 0.25, 0.5, 0.75, 0.5]);
+        assert!(controls.is_finished());
+        let events = events.collect().wait().unwrap();
+        assert_eq!(events, vec![PlayEvent::Started, PlayEvent::Looped, PlayEvent::Finished]);
+    }
+
+    #[test]
+    fn position() {
+        let pcm = PcmBuffer {
+            samples: vec![0.0; 4],
+            sample_rate: 2,
+            channels: 2
+        };
+        let controls = PlaybackControls::new();
+        let looped = PingPong::new(&pcm, 1.0);
+        let passes = looped.passes();
+        let mut source = Controlled::new(looped, controls.clone()).counting_loops(passes);
+        assert_eq!(controls.position(), None);
+        source.by_ref().take(2).for_each(drop);
+        assert_eq!(controls.position(), Some(Duration::from_millis(500)));
+        source.by_ref().take(4).for_each(drop);
+        assert_eq!(controls.position(), Some(Duration::from_millis(500)));
+        assert_eq!(controls.frames(), 3);
+    }
+
+    #[test]
+    fn region_loop() {
+        use futures::{Future, Stream};
+        let pcm = PcmBuffer {
+            samples: vec![0.0, 0.25, 0.5, 0.75, 1.0],
+            sample_rate: 5,
+            channels: 1
+        };
+        let controls = PlaybackControls::new();
+        let events = controls.subscribe();
+        let looped = RegionLoop::new(&pcm, 1.0, 1, 3);
+        let passes = looped.passes();
+        let source = Controlled::new(looped, controls.clone()).counting_loops(passes).limit_loops(3);
+        assert_eq!(source.collect::<Vec<f32>>(), vec![0.0, 0.25, 0.5, 0.25, 0.5, 0.25, 0.5]);
+        let events = events.collect().wait().unwrap();
+        assert_eq!(events, vec![PlayEvent::Started, PlayEvent::Looped, PlayEvent::Looped, PlayEvent::Finished]);
+    }
+
+    #[test]
+    fn stop_fade() {
+        let pcm = PcmBuffer {
+            samples: vec![1.0; 4],
+            sample_rate: 2,
+            channels: 2
+        };
+        let controls = PlaybackControls::new();
+        controls.set_stop_fade(Duration::from_secs(1), SoundFadeCurve::Linear);
+        let mut source = Controlled::new(PingPong::new(&pcm, 1.0), controls.clone());
+        assert_eq!(source.next(), Some(1.0));
+        controls.stop();
+        let rest: Vec<f32> = source.by_ref().take(8).collect();
+        assert_eq!(rest, vec![1.0, 0.5, 0.5]);
+        assert!(controls.is_finished());
+    }
+
+    #[test]
+    fn ping_pong_order() {
+        let pcm = PcmBuffer {
+            samples: vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5],
+            sample_rate: 44100,
+            channels: 2
+        };
+        let played: Vec<f32> = PingPong::new(&pcm, 2.0).take(14).collect();
+        assert_eq!(played, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 2.0, 3.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError, spectrum::Stft}
+};
+use image::{ColorType, png::PNGEncoder};
+
+// Levels below this are drawn in the coldest color
+const FLOOR_DB: f32 = -90.0;
+
+// The color gradient, from the quietest level to the loudest
+const GRADIENT: [[f32; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [60.0, 10.0, 110.0],
+    [190.0, 30.0, 70.0],
+    [250.0, 140.0, 10.0],
+    [255.0, 255.0, 200.0]
+];
+
+impl Sound {
+    /// Render a spectrogram of the sound clip, encoded as PNG
+    ///
+    /// Time runs from left to right across `width` columns, and frequency runs from the bottom
+    /// (0 Hz) to the top (half the sample rate) across `height` rows. Louder frequencies are drawn
+    /// in brighter colors. All channels are mixed together before the analysis.
+    ///
+    /// Only available on desktop with the `spectrogram` feature.
+    pub fn to_spectrogram(&self, width: u32, height: u32) -> Result<Vec<u8>> {
+        if width == 0 || height == 0 {
+            return Err(SoundError::InvalidParameter("A spectrogram must be at least 1x1".to_owned()).into());
+        }
+        let samples = self.pcm().to_mono();
+        let mut stft = Stft::new(height as usize * 2);
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        for x in 0..width as usize {
+            let start = x * samples.len() / width as usize;
+            let magnitudes = stft.magnitudes(&samples, start);
+            for y in 0..height as usize {
+                let magnitude = magnitudes[height as usize - 1 - y];
+                let level = (20.0 * magnitude.log10() - FLOOR_DB) / -FLOOR_DB;
+                let offset = (y * width as usize + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(&gradient(level));
+            }
+        }
+        let mut png = Vec::new();
+        PNGEncoder::new(&mut png).encode(&pixels, width, height, ColorType::RGBA(8))?;
+        Ok(png)
+    }
+}
+
+// Map a level from 0 (quietest) to 1 (loudest) onto the gradient
+fn gradient(level: f32) -> [u8; 4] {
+    let position = level.clamp(0.0, 1.0) * (GRADIENT.len() - 1) as f32;
+    let index = (position as usize).min(GRADIENT.len() - 2);
+    let blend = position - index as f32;
+    let (from, to) = (GRADIENT[index], GRADIENT[index + 1]);
+    let channel = |channel: usize| (from[channel] + (to[channel] - from[channel]) * blend) as u8;
+    [channel(0), channel(1), channel(2), 255]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    #[test]
+    fn dimensions() {
+        let samples: Vec<f32> = (0..4410).map(|index| (index as f32 * 0.1).sin()).collect();
+        let sound = Sound::from_pcm(&samples, 44100, 1).unwrap();
+        let png = sound.to_spectrogram(64, 32).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!(image.dimensions(), (64, 32));
+        assert!(sound.to_spectrogram(0, 32).is_err());
+    }
+
+    #[test]
+    fn gradient_ends() {
+        assert_eq!(gradient(-1.0), [0, 0, 0, 255]);
+        assert_eq!(gradient(1.0), [255, 255, 200, 255]);
+    }
+}
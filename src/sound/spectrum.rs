@@ -0,0 +1,72 @@
+use rustfft::{
+    FFT, FFTplanner,
+    num_complex::Complex,
+    num_traits::Zero
+};
+use std::{
+    f32::consts::PI,
+    sync::Arc
+};
+
+// Windowed Fourier analysis of fixed-size frames of mono samples
+pub(super) struct Stft {
+    fft: Arc<dyn FFT<f32>>,
+    window: Vec<f32>,
+    input: Vec<Complex<f32>>,
+    output: Vec<Complex<f32>>
+}
+
+impl Stft {
+    pub(super) fn new(size: usize) -> Stft {
+        let fft = FFTplanner::new(false).plan_fft(size);
+        Stft {
+            fft,
+            window: hann_window(size),
+            input: vec![Complex::zero(); size],
+            output: vec![Complex::zero(); size]
+        }
+    }
+
+    pub(super) fn size(&self) -> usize {
+        self.window.len()
+    }
+
+    // The magnitudes of the frequency bins from 0 up to and including the Nyquist frequency
+    //
+    // The frame starts at `start` and is padded with silence where it runs past the samples. The
+    // magnitudes are normalized so that a full-scale sine wave peaks at 1
+    pub(super) fn magnitudes(&mut self, samples: &[f32], start: usize) -> Vec<f32> {
+        let size = self.size();
+        for (index, (input, weight)) in self.input.iter_mut().zip(self.window.iter()).enumerate() {
+            let sample = samples.get(start + index).cloned().unwrap_or(0.0);
+            *input = Complex::new(sample * weight, 0.0);
+        }
+        self.fft.process(&mut self.input, &mut self.output);
+        let scale = 4.0 / size as f32;
+        self.output[..=size / 2].iter().map(|bin| bin.norm() * scale).collect()
+    }
+}
+
+fn hann_window(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|index| 0.5 - 0.5 * (2.0 * PI * index as f32 / size as f32).cos())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sine_peak() {
+        let size = 256;
+        let samples: Vec<f32> = (0..size)
+            .map(|index| (2.0 * PI * 16.0 * index as f32 / size as f32).sin())
+            .collect();
+        let magnitudes = Stft::new(size).magnitudes(&samples, 0);
+        assert_eq!(magnitudes.len(), size / 2 + 1);
+        let peak = (0..magnitudes.len()).max_by(|a, b| magnitudes[*a].partial_cmp(&magnitudes[*b]).unwrap()).unwrap();
+        assert_eq!(peak, 16);
+        assert!((magnitudes[16] - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+
+// The delays that widen a sound without being heard as an echo
+const MIN_HAAS_DELAY_MS: f32 = 1.0;
+const MAX_HAAS_DELAY_MS: f32 = 40.0;
+
+impl Sound {
+    /// Narrow or widen the stereo image of the sound clip, returning the changed clip
+    ///
+    /// The clip is split into mid and side parts, the sum and the difference of its channels, and
+    /// the side part is scaled by `width`. A width of 0 collapses the clip to mono, 1 leaves it
+    /// unchanged, and 2 makes it sound wider. Samples that would go past full scale are clipped.
+    /// Fails unless the clip is stereo and the width is at least 0.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn stereo_width(&self, width: f32) -> Result<Sound> {
+        if !self.is_stereo() {
+            return Err(SoundError::InvalidParameter(format!(
+                "Only stereo clips have a width, not clips with {} channels", self.channels())).into());
+        }
+        if !(width >= 0.0 && width.is_finite()) {
+            return Err(SoundError::InvalidParameter(format!("The width must be at least 0, not {}", width)).into());
+        }
+        let mut samples = self.pcm().samples.clone();
+        for frame in samples.chunks_mut(2) {
+            let (mid, side) = ((frame[0] + frame[1]) / 2.0, (frame[0] - frame[1]) / 2.0 * width);
+            frame[0] = (mid + side).clamp(-1.0, 1.0);
+            frame[1] = (mid - side).clamp(-1.0, 1.0);
+        }
+        self.with_samples(&samples)
+    }
+
+    /// Widen the sound clip by playing a slightly delayed copy of it on the right, returning a
+    /// stereo clip
+    ///
+    /// The clip plays on the left and the copy, `delay_ms` later, plays on the right. Delays this
+    /// short aren't heard as an echo; the ear takes the sound to come from the left but hears it
+    /// as wider, which is known as the Haas effect. Stereo clips use their left channel. The new
+    /// clip is longer by the delay, so the copy isn't cut off. Fails unless the clip is mono or
+    /// stereo and the delay is between 1 and 40 milliseconds.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn haas_effect(&self, delay_ms: f32) -> Result<Sound> {
+        if self.channels() > 2 {
+            return Err(SoundError::InvalidParameter(format!(
+                "Only mono and stereo clips can be widened, not clips with {} channels", self.channels())).into());
+        }
+        if !(MIN_HAAS_DELAY_MS..=MAX_HAAS_DELAY_MS).contains(&delay_ms) {
+            return Err(SoundError::InvalidParameter(format!(
+                "The delay must be between {} and {}ms, not {}ms", MIN_HAAS_DELAY_MS, MAX_HAAS_DELAY_MS, delay_ms)).into());
+        }
+        let pcm = self.pcm();
+        let delay = (f64::from(delay_ms) * f64::from(pcm.sample_rate) / 1000.0).round() as usize;
+        let left: Vec<f32> = pcm.samples.iter().step_by(pcm.channels as usize).cloned().collect();
+        let mut samples = vec![0.0; (left.len() + delay) * 2];
+        for (index, &sample) in left.iter().enumerate() {
+            samples[index * 2] = sample;
+            samples[(index + delay) * 2 + 1] = sample;
+        }
+        self.with_samples_in(&samples, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width() {
+        let sound = Sound::from_pcm(&[0.5, 0.25, -0.5, 0.0, 0.0, 0.75], 100, 2).unwrap();
+        for frame in sound.stereo_width(0.0).unwrap().pcm().samples.chunks(2) {
+            assert_eq!(frame[0], frame[1]);
+        }
+        assert_eq!(sound.stereo_width(1.0).unwrap().pcm(), sound.pcm());
+        let wide = sound.stereo_width(2.0).unwrap();
+        for (sample, expected) in wide.pcm().samples.iter().zip(&[0.625, 0.125, -0.75, 0.25, -0.375, 1.0]) {
+            assert!((sample - expected).abs() < 1e-3, "Got {:?}", wide.pcm().samples);
+        }
+        assert!(sound.stereo_width(-1.0).is_err());
+        assert!(Sound::from_pcm(&[0.5], 100, 1).unwrap().stereo_width(1.0).is_err());
+    }
+
+    #[test]
+    fn haas_effect() {
+        let mono = Sound::from_pcm(&[0.5, -0.25, 0.0, 0.0], 1000, 1).unwrap();
+        let wide = mono.haas_effect(2.0).unwrap();
+        assert!(wide.is_stereo());
+        let samples = &wide.pcm().samples;
+        let (left, right): (Vec<f32>, Vec<f32>) = samples.chunks(2).map(|frame| (frame[0], frame[1])).unzip();
+        assert_eq!(left[..4], mono.pcm().samples[..]);
+        assert_eq!(left[4..], [0.0, 0.0]);
+        // The right channel is the left one, two frames later
+        assert_eq!(right[..2], [0.0, 0.0]);
+        assert_eq!(right[2..], left[..4]);
+        let stereo = Sound::from_pcm(&[0.5, 0.75, -0.25, 0.75], 1000, 2).unwrap();
+        let wide = stereo.haas_effect(1.0).unwrap();
+        assert_eq!(wide.pcm().samples[..], [stereo.pcm().samples[0], 0.0, stereo.pcm().samples[2], stereo.pcm().samples[0], 0.0, stereo.pcm().samples[2]]);
+        assert!(mono.haas_effect(0.5).is_err());
+        assert!(mono.haas_effect(41.0).is_err());
+        assert!(Sound::from_pcm(&[0.0; 6], 1000, 6).unwrap().haas_effect(10.0).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use std::{f32::consts::PI, time::Duration};
+
+// The sample rate of generated tones
+const SAMPLE_RATE: u32 = 44100;
+
+/// The shape of a generated tone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Waveform {
+    /// A smooth, pure tone
+    Sine,
+    /// A hollow, buzzy tone that switches between full positive and full negative
+    Square,
+    /// A softer tone that ramps linearly up and down
+    Triangle,
+    /// A bright, harsh tone that ramps up and then drops back
+    Sawtooth
+}
+
+impl Waveform {
+    // The value of the wave at a point in its cycle, from 0 to 1
+    fn at(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0
+        }
+    }
+}
+
+/// Synthesizes simple tones, for alerts, interface feedback, or placeholder sounds
+///
+/// The tones are mono, at full scale, and sampled at 44.1 kHz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeepGenerator {
+    /// The pitch of the tone, in hertz
+    pub frequency: f32,
+    /// How long the tone lasts
+    pub duration: Duration,
+    /// The shape of the tone
+    pub waveform: Waveform
+}
+
+impl BeepGenerator {
+    /// Create a generator for a tone with the given pitch, length and shape
+    pub fn new(frequency: f32, duration: Duration, waveform: Waveform) -> BeepGenerator {
+        BeepGenerator { frequency, duration, waveform }
+    }
+
+    /// Synthesize the tone as a sound clip
+    ///
+    /// The frequency must be positive and below half the sample rate, or the tone can't be
+    /// represented.
+    pub fn generate(&self) -> Result<Sound> {
+        let nyquist = SAMPLE_RATE as f32 / 2.0;
+        if !(self.frequency > 0.0 && self.frequency < nyquist) {
+            return Err(SoundError::InvalidParameter(format!("The frequency must be between 0 and {} Hz, not {}", nyquist, self.frequency)).into());
+        }
+        let frames = (self.duration.as_secs_f64() * f64::from(SAMPLE_RATE)).round() as usize;
+        let samples: Vec<f32> = (0..frames)
+            .map(|frame| {
+                let phase = (frame as f64 * f64::from(self.frequency) / f64::from(SAMPLE_RATE)).fract();
+                self.waveform.at(phase as f32)
+            })
+            .collect();
+        Sound::from_pcm(&samples, SAMPLE_RATE, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sine_amplitude() {
+        let sound = BeepGenerator::new(440.0, Duration::from_millis(100), Waveform::Sine).generate().unwrap();
+        let samples = &sound.pcm().samples;
+        assert_eq!(samples.len(), 4410);
+        let max = samples.iter().cloned().fold(f32::MIN, f32::max);
+        let min = samples.iter().cloned().fold(f32::MAX, f32::min);
+        assert!((max - min - 2.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn waveforms() {
+        assert_eq!(Waveform::Square.at(0.25), 1.0);
+        assert_eq!(Waveform::Square.at(0.75), -1.0);
+        assert_eq!(Waveform::Triangle.at(0.5), 1.0);
+        assert_eq!(Waveform::Triangle.at(0.0), -1.0);
+        assert_eq!(Waveform::Sawtooth.at(0.0), -1.0);
+        assert!(BeepGenerator::new(0.0, Duration::from_secs(1), Waveform::Sine).generate().is_err());
+        assert!(BeepGenerator::new(30000.0, Duration::from_secs(1), Waveform::Sine).generate().is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use rustfft::{
+    FFT, FFTplanner,
+    num_complex::Complex,
+    num_traits::Zero
+};
+use std::{
+    f32::consts::PI,
+    sync::Arc
+};
+
+// The size of each analysis frame, and the distance between frames in the output
+const FRAME_SIZE: usize = 2048;
+const SYNTHESIS_HOP: usize = FRAME_SIZE / 4;
+
+impl Sound {
+    /// Change the speed of the sound clip without changing its pitch
+    ///
+    /// A factor of 2 plays twice as fast, so the new clip is half as long, and 0.5 plays at half
+    /// speed. This uses a phase vocoder, which can make transients sound smeared or phasey,
+    /// especially when slowing down a lot. Fails unless the factor is above 0 and at most 4.
+    ///
+    /// The new clip keeps the volume and looping settings of this one. Only available on desktop.
+    pub fn time_stretch(&self, factor: f32) -> Result<Sound> {
+        if !(factor > 0.0 && factor <= 4.0) {
+            return Err(SoundError::InvalidParameter(format!("The factor must be above 0 and at most 4, not {}", factor)).into());
+        }
+        let pcm = self.pcm();
+        let channels = pcm.channels as usize;
+        let frames = (pcm.frames() as f64 / f64::from(factor)).round() as usize;
+        let mut vocoder = Vocoder::new();
+        let mut samples = vec![0.0; frames * channels];
+        for channel in 0..channels {
+            let input: Vec<f32> = pcm.samples.iter().skip(channel).step_by(channels).cloned().collect();
+            for (frame, sample) in vocoder.stretch(&input, factor, frames).into_iter().enumerate() {
+                samples[frame * channels + channel] = sample;
+            }
+        }
+        self.with_samples(&samples)
+    }
+}
+
+struct Vocoder {
+    fft: Arc<dyn FFT<f32>>,
+    inverse: Arc<dyn FFT<f32>>,
+    window: Vec<f32>
+}
+
+impl Vocoder {
+    fn new() -> Vocoder {
+        Vocoder {
+            fft: FFTplanner::new(false).plan_fft(FRAME_SIZE),
+            inverse: FFTplanner::new(true).plan_fft(FRAME_SIZE),
+            window: (0..FRAME_SIZE)
+                .map(|index| 0.5 - 0.5 * (2.0 * PI * index as f32 / FRAME_SIZE as f32).cos())
+                .collect()
+        }
+    }
+
+    // Stretch mono samples into the given number of samples
+    //
+    // Frames are read every `factor` synthesis hops, and the phase of each bin is advanced by
+    // the frequency measured between frames, so the frames line up again when they are written
+    // one synthesis hop apart
+    fn stretch(&mut self, input: &[f32], factor: f32, length: usize) -> Vec<f32> {
+        let bins = FRAME_SIZE;
+        let analysis_hop = SYNTHESIS_HOP as f64 * f64::from(factor);
+        let mut output = vec![0.0; length + FRAME_SIZE];
+        let mut weights = vec![0.0; length + FRAME_SIZE];
+        let mut last_phases = vec![0.0; bins];
+        let mut phases = vec![0.0; bins];
+        let mut last_position = 0;
+        let mut frame_in = vec![Complex::zero(); bins];
+        let mut spectrum = vec![Complex::zero(); bins];
+        for frame in 0..=length / SYNTHESIS_HOP {
+            let position = (frame as f64 * analysis_hop).round() as usize;
+            for (index, (value, weight)) in frame_in.iter_mut().zip(self.window.iter()).enumerate() {
+                *value = Complex::new(input.get(position + index).cloned().unwrap_or(0.0) * weight, 0.0);
+            }
+            self.fft.process(&mut frame_in, &mut spectrum);
+            let hop = (position - last_position) as f32;
+            for (bin, value) in spectrum.iter_mut().enumerate() {
+                let phase = value.arg();
+                if frame == 0 {
+                    phases[bin] = phase;
+                } else {
+                    let expected = 2.0 * PI * bin as f32 * hop / bins as f32;
+                    let deviation = wrap_phase(phase - last_phases[bin] - expected);
+                    let frequency = if hop > 0.0 { (expected + deviation) / hop } else { 2.0 * PI * bin as f32 / bins as f32 };
+                    phases[bin] = wrap_phase(phases[bin] + frequency * SYNTHESIS_HOP as f32);
+                }
+                last_phases[bin] = phase;
+                let magnitude = value.norm();
+                *value = Complex::new(magnitude * phases[bin].cos(), magnitude * phases[bin].sin());
+            }
+            last_position = position;
+            self.inverse.process(&mut spectrum, &mut frame_in);
+            let start = frame * SYNTHESIS_HOP;
+            for (index, (value, weight)) in frame_in.iter().zip(self.window.iter()).enumerate() {
+                output[start + index] += value.re / bins as f32 * weight;
+                weights[start + index] += weight * weight;
+            }
+        }
+        output.truncate(length);
+        for (sample, weight) in output.iter_mut().zip(weights.iter()) {
+            if *weight > 1e-3 {
+                *sample /= weight;
+            }
+        }
+        output
+    }
+}
+
+// Bring a phase into the range [-pi, pi]
+fn wrap_phase(phase: f32) -> f32 {
+    phase - 2.0 * PI * (phase / (2.0 * PI)).round()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stretch() {
+        let samples: Vec<f32> = (0..16000).map(|index| 0.5 * (2.0 * PI * 440.0 * index as f32 / 16000.0).sin()).collect();
+        let sound = Sound::from_pcm(&samples, 16000, 1).unwrap();
+        let crossing_rate = |sound: &Sound| sound.zero_cross_count() as f32 / sound.duration_or_zero().as_secs_f32();
+        for &factor in &[0.5, 2.0] {
+            let stretched = sound.time_stretch(factor).unwrap();
+            let expected = sound.duration_or_zero().as_secs_f32() / factor;
+            assert!((stretched.duration_or_zero().as_secs_f32() - expected).abs() < 1e-3);
+            let ratio = crossing_rate(&stretched) / crossing_rate(&sound);
+            assert!((ratio - 1.0).abs() < 0.05, "The pitch changed by a ratio of {}", ratio);
+        }
+        assert!(sound.time_stretch(0.0).is_err());
+        assert!(sound.time_stretch(4.5).is_err());
+        assert!(sound.time_stretch(f32::NAN).is_err());
+    }
+}
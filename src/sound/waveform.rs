@@ -0,0 +1,66 @@
+use crate::{
+    Result,
+    sound::{Sound, SoundError}
+};
+use image::{ColorType, png::PNGEncoder};
+
+impl Sound {
+    /// Render the waveform of the sound clip, encoded as PNG
+    ///
+    /// Time runs from left to right across `width` columns. Each column is a vertical bar in
+    /// `color` from the lowest to the highest sample in that part of the clip, with full scale at
+    /// the top and bottom edges; the rest of the image is transparent. All channels are mixed
+    /// together first, and the volume is not applied.
+    ///
+    /// Only available on desktop.
+    pub fn export_waveform_image(&self, width: u32, height: u32, color: [u8; 4]) -> Result<Vec<u8>> {
+        if width == 0 || height == 0 {
+            return Err(SoundError::InvalidParameter("A waveform image must be at least 1x1".to_owned()).into());
+        }
+        let samples = self.pcm().to_mono();
+        let (width, height) = (width as usize, height as usize);
+        // The row that a sample is drawn at, from the top
+        let row = |sample: f32| ((1.0 - sample.clamp(-1.0, 1.0)) / 2.0 * (height - 1) as f32).round() as usize;
+        let mut pixels = vec![0; width * height * 4];
+        for x in 0..width {
+            let start = x * samples.len() / width;
+            let end = ((x + 1) * samples.len() / width).max(start + 1).min(samples.len());
+            let bucket = &samples[start.min(end)..end];
+            if bucket.is_empty() {
+                continue;
+            }
+            let lowest = bucket.iter().cloned().fold(1.0, f32::min);
+            let highest = bucket.iter().cloned().fold(-1.0, f32::max);
+            for y in row(highest)..=row(lowest) {
+                let offset = (y * width + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(&color);
+            }
+        }
+        let mut png = Vec::new();
+        PNGEncoder::new(&mut png).encode(&pixels, width as u32, height as u32, ColorType::RGBA(8))?;
+        Ok(png)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    #[test]
+    fn bars() {
+        let samples: Vec<f32> = (0..400).map(|index| if index < 200 { 0.0 } else if index % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let sound = Sound::from_pcm(&samples, 8000, 1).unwrap();
+        let red = [255, 0, 0, 255];
+        let png = sound.export_waveform_image(8, 5, red).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!(image.dimensions(), (8, 5));
+        let image = image.to_rgba();
+        // Silence is a single line across the middle, and the loud half fills the whole height
+        assert_eq!(image.get_pixel(0, 2).data, red);
+        assert_eq!(image.get_pixel(0, 0).data, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(7, 0).data, red);
+        assert_eq!(image.get_pixel(7, 4).data, red);
+        assert!(sound.export_waveform_image(8, 0, red).is_err());
+    }
+}